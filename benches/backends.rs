@@ -0,0 +1,88 @@
+//! Compares the backends on the `Page` example of README, scaled to thousands of articles.
+//! Run with `cargo bench --features backend-tl`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use h2s::backend::scraper::Scraper;
+use h2s::backend::tl::Tl;
+use h2s::extraction_method::ExtractNthText;
+use h2s::FromHtml;
+
+#[derive(FromHtml, Debug, Eq, PartialEq)]
+pub struct Page {
+    #[h2s(attr = "lang")]
+    lang: String,
+    #[h2s(select = "div > h1.blog-title")]
+    blog_title: String,
+    #[h2s(select = ".articles > div")]
+    articles: Vec<Article>,
+    #[h2s(select = "body", extractor = ExtractNthText(1))]
+    footer2: String,
+}
+
+#[derive(FromHtml, Debug, Eq, PartialEq)]
+pub struct Article {
+    #[h2s(select = "h2 > a")]
+    title: String,
+    #[h2s(select = "div > span")]
+    view_count: usize,
+    #[h2s(select = "h2 > a", attr = "href")]
+    url: String,
+    #[h2s(select = "ul > li")]
+    tags: Vec<String>,
+    #[h2s(select = "ul > li:nth-child(1)")]
+    first_tag: Option<String>,
+}
+
+fn page_html(articles: usize) -> String {
+    let articles = (0..articles)
+        .map(|i| {
+            format!(
+                r#"
+          <div>
+              <h2><a href="https://example.com/{i}">article{i}</a></h2>
+              <div><span>{i}</span> Views</div>
+              <ul><li>Tag1</li><li>Tag2</li></ul>
+          </div>"#
+            )
+        })
+        .collect::<String>();
+    format!(
+        r#"
+<html lang="en">
+<body>
+  <div>
+      <h1 class="blog-title">My tech blog</h1>
+      <div class="articles">{articles}
+      </div>
+  </div>
+  footer1
+  <hr />
+  footer2
+</body>
+</html>
+"#
+    )
+}
+
+fn backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse page");
+    group.sample_size(10);
+    for articles in [1000, 5000] {
+        let html = page_html(articles);
+        // both backends must give the same result to be compared
+        assert_eq!(
+            h2s::parse_with_backend::<Page, Scraper>(&html).unwrap(),
+            h2s::parse_with_backend::<Page, Tl>(&html).unwrap()
+        );
+        group.bench_with_input(BenchmarkId::new("scraper", articles), &html, |b, html| {
+            b.iter(|| h2s::parse_with_backend::<Page, Scraper>(html).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("tl", articles), &html, |b, html| {
+            b.iter(|| h2s::parse_with_backend::<Page, Tl>(html).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, backends);
+criterion_main!(benches);
@@ -3,17 +3,22 @@
 
 use std::fmt::{Display, Formatter};
 
-use crate::element_selector::{Root, Select};
+use crate::element_selector::{InvalidSelector, Root, Select};
 use crate::extraction_method::{
-    AttributeNotFound, ExtractAttribute, ExtractInnerText, ExtractNthText, ExtractionMethod, NoOp,
-    NotFound,
+    AttributeNotFound, ExtractAttribute, ExtractDefault, ExtractInnerHtml, ExtractInnerText,
+    ExtractNthText, ExtractOuterHtml, ExtractOwnText, ExtractionMethod, NoOp, NotFound,
 };
 use crate::functor::ExactlyOne;
-use crate::macro_utils::{ExtractionError, ParseError, ProcessError, TransformError};
+use crate::html::SourceSpan;
+use crate::macro_utils::{
+    ErasedExtractionMethod, ExtractionError, ExtractorError, ParseError, ParseWithError,
+    ProcessError, TransformError,
+};
+use crate::parseable::UnknownEnumValue;
 use crate::transformable::{VecToArrayError, VecToOptionError, VecToSingleError};
 use crate::traversable_with_context::{Context, ListIndex, NoContext};
 use crate::Never;
-use crate::{Error, FieldError};
+use crate::{CollectedErrors, Error, FieldError, RejectedVariant, RejectionReason, VariantError};
 
 impl Display for VecToSingleError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -71,9 +76,64 @@ impl Display for AttributeNotFound {
     }
 }
 
+impl Display for UnknownEnumValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown value \"{}\": expected one of ", self.value)?;
+        for (i, accepted) in self.accepted.iter().enumerate() {
+            write!(f, "{}\"{}\"", if i == 0 { "" } else { ", " }, accepted)?;
+        }
+        Ok(())
+    }
+}
+
 impl Display for FieldError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.field_name, self.error)
+        if self.field_name.is_empty() {
+            write!(f, "{}", self.error)
+        } else {
+            write!(f, "{}: {}", self.field_name, self.error)
+        }
+    }
+}
+
+impl Display for VariantError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no variant matched")?;
+        for (i, rejected) in self.rejected.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { ": " } else { ", " }, rejected)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E> Display for CollectedErrors<E>
+where
+    E: Error,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} error(s) occurred", self.errors.len())?;
+        for (i, error) in self.errors.iter().enumerate() {
+            write!(f, "{}[{}]", if i == 0 { ": " } else { ", " }, error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RejectedVariant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}: {}]", self.variant_name, self.reason)
+    }
+}
+
+impl Display for RejectionReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectionReason::ConditionNotMatched { selector } => {
+                write!(f, "the element doesn't match \"{selector}\"")
+            }
+            RejectionReason::FieldError(e) => write!(f, "{e}"),
+            RejectionReason::InvalidSelector(e) => write!(f, "{e}"),
+        }
     }
 }
 
@@ -85,6 +145,7 @@ where
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::InvalidSelector(e) => write!(f, "{}", e),
             Self::TransformError(e) => write!(f, "{}", e),
             Self::ExtractionError(e) => write!(f, "{}", e),
             Self::ParseError(e) => write!(f, "{}", e),
@@ -122,10 +183,11 @@ where
     M: ExtractionMethod,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_location(f, &self.context, &self.span, &self.snippet)?;
         write!(
             f,
-            "{}: failed to extract value of {}: {}",
-            self.context, self.extraction_method, self.error
+            ": failed to extract value of {}: {}",
+            self.extraction_method, self.error
         )
     }
 }
@@ -137,7 +199,67 @@ where
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         // Just displaying an inner error because error is originally caused at more inside of the inner struct
-        write!(f, "{}: {}", self.context, self.error)
+        write_location(f, &self.context, &self.span, &self.snippet)?;
+        write!(f, ": {}", self.error)
+    }
+}
+
+impl Display for ErasedExtractionMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for ExtractorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for ParseWithError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for SourceSpan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ", col {}", column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the context followed by the location and the snippet of the element if they're known
+fn write_location<C>(
+    f: &mut Formatter<'_>,
+    context: &C,
+    span: &Option<SourceSpan>,
+    snippet: &Option<String>,
+) -> std::fmt::Result
+where
+    C: Context,
+{
+    let mut parts = vec![context.to_string()];
+    if let Some(span) = span {
+        parts.push(format!("at {}", span));
+    }
+    if let Some(snippet) = snippet {
+        parts.push(format!("({})", snippet));
+    }
+    parts.retain(|p| !p.is_empty());
+    write!(f, "{}", parts.join(" "))
+}
+
+impl Display for InvalidSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid css selector \"{}\": {}",
+            self.selector, self.message
+        )
     }
 }
 
@@ -158,11 +280,31 @@ impl Display for ExtractAttribute {
         write!(f, "attribute={}", self.name)
     }
 }
+impl<P> Display for ExtractDefault<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "default")
+    }
+}
 impl Display for ExtractInnerText {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "inner text")
     }
 }
+impl Display for ExtractOwnText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "own text")
+    }
+}
+impl Display for ExtractInnerHtml {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "inner html")
+    }
+}
+impl Display for ExtractOuterHtml {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "outer html")
+    }
+}
 impl Display for NoOp {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "no-op")
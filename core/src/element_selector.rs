@@ -1,42 +1,184 @@
 use crate::functor::{ExactlyOne, Functor};
 use crate::html::{CssSelector, HtmlElement};
-use std::fmt::{Debug, Display};
+use std::any::{Any, TypeId};
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::{Arc, RwLock};
 
 pub trait TargetElementSelector: Debug + Display {
     type Output<E>: Functor<Inner = E>;
-    fn select<E>(&self, n: &E) -> Self::Output<E>
+    fn select<E>(&self, n: &E) -> Result<Self::Output<E>, InvalidSelector>
     where
         E: HtmlElement;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Select {
     pub selector: String,
+    cache: Option<&'static SelectorCache>,
+}
+
+impl Select {
+    /// A selector that is parsed on every selection
+    pub fn new<S>(selector: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            selector: selector.into(),
+            cache: None,
+        }
+    }
+
+    /// A selector that is parsed only once per backend, and shared by every selection
+    pub fn cached(cache: &'static SelectorCache) -> Self {
+        Self {
+            selector: cache.selector.to_string(),
+            cache: Some(cache),
+        }
+    }
+
+    /// Parses the selector for the backend of the element type, or gets the cached one
+    pub fn compile<E>(&self) -> Result<Arc<E::Selector>, InvalidSelector>
+    where
+        E: HtmlElement,
+    {
+        match self.cache {
+            Some(cache) => cache.get::<E::Selector>(),
+            None => parse_selector(&self.selector).map(Arc::new),
+        }
+    }
 }
 
 impl TargetElementSelector for Select {
     type Output<E> = Vec<E>;
 
-    fn select<E>(&self, n: &E) -> Self::Output<E>
+    fn select<E>(&self, n: &E) -> Result<Self::Output<E>, InvalidSelector>
     where
         E: HtmlElement,
     {
-        // TODO cache selector
-        let selector = E::Selector::parse(&self.selector).unwrap();
-        n.select(&selector)
+        Ok(n.select(&*self.compile::<E>()?))
+    }
+}
+
+/// A css selector that is parsed lazily and only once for each backend.
+/// It's intended to be used as a static variable, so that each field parses its selector only once
+pub struct SelectorCache {
+    selector: &'static str,
+    compiled: RwLock<Vec<(TypeId, Arc<dyn Any + Send + Sync>)>>,
+}
+
+impl SelectorCache {
+    pub const fn new(selector: &'static str) -> Self {
+        Self {
+            selector,
+            compiled: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn get<S>(&self) -> Result<Arc<S>, InvalidSelector>
+    where
+        S: CssSelector,
+    {
+        let type_id = TypeId::of::<S>();
+        let find = |compiled: &[(TypeId, Arc<dyn Any + Send + Sync>)]| {
+            compiled
+                .iter()
+                .find(|(id, _)| *id == type_id)
+                .and_then(|(_, s)| s.clone().downcast::<S>().ok())
+        };
+        // the cache is just a cache, so it's still usable even if another thread panicked
+        if let Some(s) = find(&self.compiled.read().unwrap_or_else(|e| e.into_inner())) {
+            return Ok(s);
+        }
+        let s = Arc::new(parse_selector::<S>(self.selector)?);
+        let mut compiled = self.compiled.write().unwrap_or_else(|e| e.into_inner());
+        if find(&compiled).is_none() {
+            compiled.push((type_id, s.clone()));
+        }
+        Ok(s)
+    }
+}
+
+impl Debug for SelectorCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SelectorCache")
+            .field("selector", &self.selector)
+            .finish_non_exhaustive()
     }
 }
 
+fn parse_selector<S>(selector: &str) -> Result<S, InvalidSelector>
+where
+    S: CssSelector,
+{
+    S::parse(selector).map_err(|e| InvalidSelector {
+        selector: selector.to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// A css selector that the backend failed to parse
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidSelector {
+    pub selector: String,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub struct Root;
 
 impl TargetElementSelector for Root {
     type Output<E> = ExactlyOne<E>;
 
-    fn select<E>(&self, n: &E) -> Self::Output<E>
+    fn select<E>(&self, n: &E) -> Result<Self::Output<E>, InvalidSelector>
     where
         E: HtmlElement,
     {
-        ExactlyOne(n.clone())
+        Ok(ExactlyOne(n.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static PARSED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug)]
+    struct CountingSelector;
+
+    impl CssSelector for CountingSelector {
+        type Error = std::fmt::Error;
+
+        fn parse<S>(s: S) -> Result<Self, Self::Error>
+        where
+            S: AsRef<str>,
+        {
+            PARSED.fetch_add(1, Ordering::SeqCst);
+            if s.as_ref().is_empty() {
+                Err(std::fmt::Error)
+            } else {
+                Ok(CountingSelector)
+            }
+        }
+    }
+
+    #[test]
+    fn selector_cache() {
+        static CACHE: SelectorCache = SelectorCache::new("div");
+        let before = PARSED.load(Ordering::SeqCst);
+        assert!(CACHE.get::<CountingSelector>().is_ok());
+        assert!(CACHE.get::<CountingSelector>().is_ok());
+        assert_eq!(PARSED.load(Ordering::SeqCst) - before, 1);
+
+        static INVALID: SelectorCache = SelectorCache::new("");
+        assert_eq!(
+            INVALID.get::<CountingSelector>().unwrap_err(),
+            InvalidSelector {
+                selector: "".to_string(),
+                message: "an error occurred when formatting an argument".to_string(),
+            }
+        );
     }
 }
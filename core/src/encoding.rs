@@ -0,0 +1,314 @@
+//! Character encoding detection of HTML bytes, following the encoding sniffing algorithm of the HTML spec.
+//! <https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding>
+
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252, X_USER_DEFINED};
+
+/// How many bytes are scanned to find `<meta>` declaring the encoding, which is the same as browsers
+const PRESCAN_LENGTH: usize = 1024;
+
+/// Detects the encoding of the HTML and decodes it.
+/// The encoding is determined by the BOM, then `<meta charset>` or `<meta http-equiv="Content-Type">`, then `hint`.
+/// If none of them is found, it's UTF-8 if the bytes are valid as UTF-8, otherwise windows-1252.
+/// Malformed byte sequences are replaced with U+FFFD
+pub fn decode<'a>(
+    bytes: &'a [u8],
+    hint: Option<&'static Encoding>,
+) -> (Cow<'a, str>, &'static Encoding) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (text, encoding);
+    }
+    let encoding = sniff(bytes, hint);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text, encoding)
+}
+
+/// Detects the encoding of the HTML in the same way as `decode`
+pub fn sniff(bytes: &[u8], hint: Option<&'static Encoding>) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    prescan(&bytes[..bytes.len().min(PRESCAN_LENGTH)])
+        .or(hint)
+        .unwrap_or_else(|| {
+            if std::str::from_utf8(bytes).is_ok() {
+                UTF_8
+            } else {
+                WINDOWS_1252
+            }
+        })
+}
+
+/// Finds the encoding declared by `<meta>`.
+/// <https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding>
+fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        if rest.starts_with(b"<!--") {
+            // `-->` may share the dashes with `<!--`, like `<!-->`
+            pos += 2 + find(&rest[2..], b"-->").map_or(rest.len(), |i| i + 3);
+        } else if starts_with_ignore_case(rest, b"<meta")
+            && rest.get(5).map_or(false, |&b| is_space(b) || b == b'/')
+        {
+            pos += 6;
+            if let Some(encoding) = meta_encoding(bytes, &mut pos) {
+                return Some(encoding);
+            }
+        } else if rest.len() >= 2
+            && rest[0] == b'<'
+            && (rest[1].is_ascii_alphabetic()
+                || (rest[1] == b'/' && rest.get(2).map_or(false, u8::is_ascii_alphabetic)))
+        {
+            // other tags are skipped with their attributes, whose values may contain `>`
+            pos += 1 + rest[1..]
+                .iter()
+                .position(|&b| is_space(b) || b == b'>')
+                .unwrap_or(rest.len() - 1);
+            while attribute(bytes, &mut pos).is_some() {}
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            pos += find(rest, b">").map_or(rest.len(), |i| i + 1);
+        } else {
+            pos += 1;
+        }
+    }
+    None
+}
+
+/// The encoding declared by the attributes of `<meta>`, where `pos` is just after `<meta`
+fn meta_encoding(bytes: &[u8], pos: &mut usize) -> Option<&'static Encoding> {
+    let mut seen = Vec::new();
+    let mut got_pragma = false;
+    // `Some(true)` if the encoding is declared by `content`, which is valid only with `http-equiv`,
+    // and `Some(false)` if by `charset`
+    let mut need_pragma = None;
+    let mut charset = None;
+    while let Some((name, value)) = attribute(bytes, pos) {
+        if seen.contains(&name) {
+            continue;
+        }
+        match name.as_slice() {
+            b"http-equiv" => got_pragma = value.eq_ignore_ascii_case(b"content-type"),
+            b"content" if charset.is_none() => {
+                if let Some(encoding) = charset_in_content(&value) {
+                    charset = Some(encoding);
+                    need_pragma = Some(true);
+                }
+            }
+            b"charset" if charset.is_none() => {
+                charset = Encoding::for_label(&value);
+                need_pragma = Some(false);
+            }
+            _ => {}
+        }
+        seen.push(name);
+    }
+    match (need_pragma, charset) {
+        (Some(true), _) if !got_pragma => None,
+        (Some(_), Some(encoding)) if encoding == UTF_16BE || encoding == UTF_16LE => Some(UTF_8),
+        (Some(_), Some(encoding)) if encoding == X_USER_DEFINED => Some(WINDOWS_1252),
+        (Some(_), charset) => charset,
+        (None, _) => None,
+    }
+}
+
+/// Gets an attribute with the lowercased name and value, or `None` at the end of the tag.
+/// <https://html.spec.whatwg.org/multipage/parsing.html#concept-get-attributes-when-sniffing>
+fn attribute(bytes: &[u8], pos: &mut usize) -> Option<(Vec<u8>, Vec<u8>)> {
+    let byte = |pos: usize| bytes.get(pos).copied();
+    while byte(*pos).map_or(false, |b| is_space(b) || b == b'/') {
+        *pos += 1;
+    }
+    if byte(*pos).map_or(true, |b| b == b'>') {
+        return None;
+    }
+
+    let mut name = Vec::new();
+    loop {
+        match byte(*pos)? {
+            b'=' if !name.is_empty() => break,
+            b if is_space(b) => {
+                while byte(*pos).map_or(false, is_space) {
+                    *pos += 1;
+                }
+                if byte(*pos)? != b'=' {
+                    return Some((name, Vec::new()));
+                }
+                break;
+            }
+            b'/' | b'>' => return Some((name, Vec::new())),
+            b => name.push(b.to_ascii_lowercase()),
+        }
+        *pos += 1;
+    }
+    // skip `=` and the spaces after it
+    *pos += 1;
+    while byte(*pos).map_or(false, is_space) {
+        *pos += 1;
+    }
+
+    let mut value = Vec::new();
+    match byte(*pos)? {
+        quote @ (b'"' | b'\'') => {
+            *pos += 1;
+            loop {
+                match byte(*pos)? {
+                    b if b == quote => {
+                        *pos += 1;
+                        return Some((name, value));
+                    }
+                    b => value.push(b.to_ascii_lowercase()),
+                }
+                *pos += 1;
+            }
+        }
+        b'>' => Some((name, value)),
+        _ => {
+            while let Some(b) = byte(*pos) {
+                if is_space(b) || b == b'>' {
+                    break;
+                }
+                value.push(b.to_ascii_lowercase());
+                *pos += 1;
+            }
+            Some((name, value))
+        }
+    }
+}
+
+/// The encoding in `content` like `text/html; charset=shift_jis`.
+/// <https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element>
+fn charset_in_content(content: &[u8]) -> Option<&'static Encoding> {
+    let mut rest = content;
+    loop {
+        let i = find(rest, b"charset")?;
+        rest = &rest[i + b"charset".len()..];
+        let trimmed = trim_start(rest);
+        if let Some(value) = trimmed.strip_prefix(b"=") {
+            rest = trim_start(value);
+            break;
+        }
+        rest = trimmed;
+    }
+    let value = match rest.first()? {
+        quote @ (b'"' | b'\'') => {
+            let value = &rest[1..];
+            &value[..value.iter().position(|b| b == quote)?]
+        }
+        _ => {
+            let end = rest
+                .iter()
+                .position(|&b| is_space(b) || b == b';')
+                .unwrap_or(rest.len());
+            &rest[..end]
+        }
+    };
+    Encoding::for_label(value)
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b'\t' | b'\n' | b'\x0c' | b'\r' | b' ')
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|&b| !is_space(b))
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes.windows(pattern.len()).position(|w| w == pattern)
+}
+
+fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes.len() >= prefix.len() && bytes[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use encoding_rs::{EUC_KR, SHIFT_JIS, UTF_16LE};
+
+    #[test]
+    fn sniff_encoding() {
+        let sniff = |html: &[u8]| sniff(html, None).name();
+
+        // BOM takes precedence
+        assert_eq!(sniff(b"\xef\xbb\xbf<meta charset=shift_jis>"), "UTF-8");
+        assert_eq!(sniff(b"\xff\xfe<\x00"), "UTF-16LE");
+
+        assert_eq!(sniff(br#"<meta charset="Shift_JIS">"#), "Shift_JIS");
+        assert_eq!(sniff(b"<META CHARSET=euc-kr>"), "EUC-KR");
+        assert_eq!(
+            sniff(br#"<html><head><meta http-equiv="Content-Type" content="text/html; charset=windows-1252"></head>"#),
+            "windows-1252"
+        );
+        assert_eq!(
+            sniff(br#"<meta content='text/html; charset="euc-jp"' http-equiv=content-type>"#),
+            "EUC-JP"
+        );
+        assert_eq!(
+            sniff(br#"<meta content="text/html; charset=euc-jp">"#),
+            "UTF-8",
+            "content is ignored without http-equiv"
+        );
+        assert_eq!(
+            sniff(br#"<meta charset="utf-16le">"#),
+            "UTF-8",
+            "UTF-16 is declared by mistake since <meta> can't be read in UTF-16"
+        );
+        assert_eq!(sniff(br#"<meta charset="unknown">"#), "UTF-8");
+
+        // `<meta>` in comments or attribute values is ignored
+        assert_eq!(
+            sniff(br#"<!-- <meta charset="shift_jis"> --><meta charset="euc-kr">"#),
+            "EUC-KR"
+        );
+        assert_eq!(
+            sniff(br#"<div title='<meta charset="shift_jis">'><meta charset="euc-kr">"#),
+            "EUC-KR"
+        );
+
+        // `<meta>` after the first 1024 bytes is ignored
+        let mut html = vec![b' '; 1024];
+        html.extend_from_slice(br#"<meta charset="shift_jis">"#);
+        assert_eq!(sniff(&html), "UTF-8");
+
+        // fallback
+        assert_eq!(sniff("<p>あ</p>".as_bytes()), "UTF-8");
+        assert_eq!(sniff(b"<p>caf\xe9</p>"), "windows-1252");
+    }
+
+    #[test]
+    fn hint() {
+        assert_eq!(sniff(b"<p>\x82\xa0</p>", Some(SHIFT_JIS)), SHIFT_JIS);
+        assert_eq!(
+            sniff(br#"<meta charset="euc-kr">"#, Some(SHIFT_JIS)),
+            EUC_KR,
+            "<meta> takes precedence over the hint"
+        );
+        assert_eq!(
+            sniff(b"\xff\xfe<\x00", Some(SHIFT_JIS)),
+            UTF_16LE,
+            "BOM takes precedence over the hint"
+        );
+    }
+
+    #[test]
+    fn decode_bytes() {
+        let (text, encoding) = decode(b"<meta charset=shift_jis><p>\x82\xa0</p>", None);
+        assert_eq!(text, "<meta charset=shift_jis><p>あ</p>");
+        assert_eq!(encoding, SHIFT_JIS);
+
+        let (text, encoding) = decode(b"\xef\xbb\xbf<p>a</p>", None);
+        assert_eq!(text, "<p>a</p>", "BOM is removed");
+        assert_eq!(encoding, UTF_8);
+
+        let (text, _) = decode(b"<meta charset=utf-8><p>\xff</p>", None);
+        assert_eq!(text, "<meta charset=utf-8><p>\u{fffd}</p>");
+    }
+}
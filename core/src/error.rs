@@ -2,21 +2,30 @@
 
 use std::fmt::{Debug, Display};
 
-use crate::element_selector::TargetElementSelector;
+use crate::element_selector::{InvalidSelector, TargetElementSelector};
 use crate::extraction_method::{AttributeNotFound, ExtractionMethod, NotFound};
 use crate::functor::ExactlyOne;
-use crate::macro_utils::{ExtractionError, ParseError, ProcessError, TransformError};
+use crate::macro_utils::{
+    ExtractionError, ExtractorError, ParseError, ParseWithError, ProcessError, TransformError,
+};
+use crate::parseable::UnknownEnumValue;
 use crate::transformable::{VecToArrayError, VecToOptionError, VecToSingleError};
 use crate::traversable_with_context::Context;
-use crate::{Error, FieldError, Never};
+use crate::{CollectedErrors, Error, FieldError, Never, VariantError};
 
 impl Error for VecToArrayError {}
 impl Error for VecToSingleError {}
 impl Error for VecToOptionError {}
 impl Error for AttributeNotFound {}
+impl Error for UnknownEnumValue {}
 impl<E> Error for ExactlyOne<E> where E: Error {}
 impl Error for Never {}
 impl Error for FieldError {}
+impl Error for VariantError {}
+impl Error for ParseWithError {}
+impl Error for ExtractorError {}
+impl Error for InvalidSelector {}
+impl<E> Error for CollectedErrors<E> where E: Error {}
 
 impl<S, E> Error for TransformError<S, E>
 where
@@ -1,13 +1,20 @@
 use crate::html::{HtmlElement, HtmlNode, TextNode};
-use crate::parseable::ExtractedValue;
+use crate::parseable::{ExtractedValue, Parseable};
 use crate::{Error, Never};
-use std::fmt::{Debug, Display};
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
 
 pub trait ExtractionMethod: Debug + Display {
     type Error: Error;
-    type ExtractedValue<N: HtmlElement>: ExtractedValue;
+    type ExtractedValue<N: HtmlElement>: ExtractedValue<N>;
 
     fn extract<N: HtmlElement>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>;
+
+    /// Whether the error means that the value to extract doesn't exist in the element.
+    /// A field with `default` attribute falls back to the default value on such error.
+    fn is_missing(_error: &Self::Error) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -25,6 +32,49 @@ impl ExtractionMethod for NoOp {
     }
 }
 
+/// Extracts the input value of the `Parseable` type `P` in its default way,
+/// e.g. the element itself for a nested struct, or the inner text for a `String`
+pub struct ExtractDefault<P>(PhantomData<fn() -> P>);
+
+impl<P> ExtractDefault<P> {
+    pub fn new() -> Self {
+        ExtractDefault(PhantomData)
+    }
+}
+
+impl<P> Default for ExtractDefault<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Clone for ExtractDefault<P> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Debug for ExtractDefault<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ExtractDefault")
+    }
+}
+
+impl<P> ExtractionMethod for ExtractDefault<P>
+where
+    P: Parseable,
+{
+    type Error = Never;
+    type ExtractedValue<N: HtmlElement> = P::Input<N>;
+
+    fn extract<N>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
+    where
+        N: HtmlElement,
+    {
+        <P::Input<N> as ExtractedValue<N>>::default_method().extract(element)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ExtractInnerText;
 
@@ -40,6 +90,63 @@ impl ExtractionMethod for ExtractInnerText {
     }
 }
 
+/// Extracts the text of the direct text children, without the texts of the descendant elements.
+/// e.g. "Views" from `<div><span>901</span> Views</div>`
+#[derive(Debug, Clone)]
+pub struct ExtractOwnText;
+
+impl ExtractionMethod for ExtractOwnText {
+    type Error = Never;
+    type ExtractedValue<N: HtmlElement> = String;
+
+    fn extract<N>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
+    where
+        N: HtmlElement,
+    {
+        let text = element
+            .child_nodes()
+            .iter()
+            .filter_map(|n| match n {
+                HtmlNode::Text(text) => Some(text.get_text()),
+                _ => None,
+            })
+            .collect::<String>();
+        Ok(text.trim().to_string())
+    }
+}
+
+/// Extracts the HTML of the descendants, without the element itself
+#[derive(Debug, Clone)]
+pub struct ExtractInnerHtml;
+
+impl ExtractionMethod for ExtractInnerHtml {
+    type Error = Never;
+    type ExtractedValue<N: HtmlElement> = String;
+
+    fn extract<N>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
+    where
+        N: HtmlElement,
+    {
+        Ok(element.inner_html())
+    }
+}
+
+/// Extracts the HTML of the element itself and its descendants
+#[derive(Debug, Clone)]
+pub struct ExtractOuterHtml;
+
+impl ExtractionMethod for ExtractOuterHtml {
+    type Error = Never;
+    type ExtractedValue<N: HtmlElement> = String;
+
+    fn extract<N>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
+    where
+        N: HtmlElement,
+    {
+        Ok(element.outer_html())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ExtractAttribute {
     pub name: String,
@@ -49,7 +156,7 @@ impl ExtractionMethod for ExtractAttribute {
     type Error = AttributeNotFound;
     type ExtractedValue<N: HtmlElement> = String;
 
-    fn extract<N: HtmlElement>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
+    fn extract<N>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
     where
         N: HtmlElement,
     {
@@ -60,6 +167,10 @@ impl ExtractionMethod for ExtractAttribute {
                 name: self.name.clone(),
             })
     }
+
+    fn is_missing(_error: &Self::Error) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -68,7 +179,7 @@ pub struct AttributeNotFound {
 }
 
 /// Extracts nth text node's text
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExtractNthText(pub usize);
 
 impl ExtractionMethod for ExtractNthText {
@@ -90,6 +201,10 @@ impl ExtractionMethod for ExtractNthText {
             .map(|s| s.trim().to_string())
             .ok_or(NotFound)
     }
+
+    fn is_missing(_error: &Self::Error) -> bool {
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -60,3 +60,44 @@ where
         wrapped
     }
 }
+
+/// A field value which consists of `T` values, like `FieldValue` but `T` doesn't have to be `Parseable`.
+/// It's used for a field whose values are parsed by a user-specified function.
+pub trait FieldValueOf<T>: Sized {
+    /// An intermediate representation of the field value
+    type Wrapped: FunctorWithContext<Inner = T>;
+
+    fn finalize(wrapped: <Self::Wrapped as Functor>::Structure<T>) -> Self;
+}
+
+impl<T> FieldValueOf<T> for T {
+    type Wrapped = ExactlyOne<T>;
+
+    fn finalize(wrapped: Self::Wrapped) -> Self {
+        wrapped.0
+    }
+}
+
+impl<T> FieldValueOf<T> for Option<T> {
+    type Wrapped = Self;
+
+    fn finalize(wrapped: Self::Wrapped) -> Self {
+        wrapped
+    }
+}
+
+impl<T> FieldValueOf<T> for Vec<T> {
+    type Wrapped = Self;
+
+    fn finalize(wrapped: Self::Wrapped) -> Self {
+        wrapped
+    }
+}
+
+impl<T, const M: usize> FieldValueOf<T> for [T; M] {
+    type Wrapped = Self;
+
+    fn finalize(wrapped: Self::Wrapped) -> Self {
+        wrapped
+    }
+}
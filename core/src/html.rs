@@ -1,5 +1,10 @@
 use std::error::Error;
 use std::fmt::Debug;
+use std::ops::Range;
+
+use encoding_rs::Encoding;
+
+use crate::encoding;
 
 /// HTML document
 pub trait HtmlDocument: Debug {
@@ -19,16 +24,148 @@ pub trait HtmlElement: Sized + Debug + Clone {
         Self: 'a;
 
     fn select(&self, selector: &Self::Selector) -> Vec<Self>;
+    /// Whether the element itself matches the selector
+    fn matches(&self, selector: &Self::Selector) -> bool;
     // TODO remove this method
     fn text_contents(&self) -> Self::TextContents<'_>;
     fn attribute<S>(&self, attr: S) -> Option<&str>
     where
         S: AsRef<str>;
     fn child_nodes(&self) -> Vec<HtmlNode<'_, Self::Backend>>;
+    /// Where the element is located in the source HTML, if the backend knows it
+    fn source_span(&self) -> Option<SourceSpan> {
+        None
+    }
+    /// Serializes the element itself and its descendants
+    fn outer_html(&self) -> String;
+    /// Serializes the descendants of the element, without the element itself.
+    ///
+    /// By default, the child elements are serialized by `outer_html` and the texts are escaped,
+    /// while the other nodes like comments are omitted.
+    /// The texts of raw text elements like `<script>` are escaped too, so a backend should override it if it can.
+    fn inner_html(&self) -> String {
+        self.child_nodes()
+            .into_iter()
+            .map(|node| match node {
+                HtmlNode::Element(element) => element.outer_html(),
+                HtmlNode::Text(text) => escape_text(&text.get_text()),
+                HtmlNode::Document(_) | HtmlNode::Other => String::new(),
+            })
+            .collect()
+    }
+    /// An HTML snippet of the element to be shown in errors, which is disabled by default
+    fn error_snippet(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Escapes a text node in the way of serializing HTML
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
-/// CSS Selector
-pub trait CssSelector: Sized {
+/// An element that shows its HTML snippet in errors, truncated to the max length.
+/// The elements selected from it are also wrapped
+#[derive(Debug, Clone)]
+pub struct WithSnippet<E> {
+    element: E,
+    max_len: usize,
+}
+
+impl<E> WithSnippet<E> {
+    pub fn new(element: E, max_len: usize) -> Self {
+        Self { element, max_len }
+    }
+}
+
+impl<E> HtmlElement for WithSnippet<E>
+where
+    E: HtmlElement,
+{
+    type Backend = E::Backend;
+    type Selector = E::Selector;
+    type TextContents<'a>
+        = E::TextContents<'a>
+    where
+        Self: 'a;
+
+    fn select(&self, selector: &Self::Selector) -> Vec<Self> {
+        self.element
+            .select(selector)
+            .into_iter()
+            .map(|element| Self::new(element, self.max_len))
+            .collect()
+    }
+
+    fn matches(&self, selector: &Self::Selector) -> bool {
+        self.element.matches(selector)
+    }
+
+    fn text_contents(&self) -> Self::TextContents<'_> {
+        self.element.text_contents()
+    }
+
+    fn attribute<S>(&self, attr: S) -> Option<&str>
+    where
+        S: AsRef<str>,
+    {
+        self.element.attribute(attr)
+    }
+
+    fn child_nodes(&self) -> Vec<HtmlNode<'_, Self::Backend>> {
+        self.element.child_nodes()
+    }
+
+    fn source_span(&self) -> Option<SourceSpan> {
+        self.element.source_span()
+    }
+
+    fn outer_html(&self) -> String {
+        self.element.outer_html()
+    }
+
+    fn inner_html(&self) -> String {
+        self.element.inner_html()
+    }
+
+    fn error_snippet(&self) -> Option<String> {
+        let mut html = self.element.outer_html();
+        if html.len() > self.max_len {
+            let mut end = self.max_len;
+            while !html.is_char_boundary(end) {
+                end -= 1;
+            }
+            html.truncate(end);
+            html.push_str("...");
+        }
+        Some(html)
+    }
+}
+
+/// A location of an element in the source HTML
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SourceSpan {
+    /// 1-based line number where the element starts
+    pub line: usize,
+    /// 1-based column number where the element starts, if the backend knows it
+    pub column: Option<usize>,
+    /// Byte range of the element in the source HTML, if the backend knows it
+    pub byte_range: Option<Range<usize>>,
+}
+
+/// CSS Selector.
+/// It's shared between threads since a parsed selector is cached in a static variable
+pub trait CssSelector: Sized + Send + Sync + 'static {
     type Error: Error;
     fn parse<S>(s: S) -> Result<Self, Self::Error>
     where
@@ -58,4 +195,26 @@ pub trait Backend: Debug {
     fn parse_document<S>(s: S) -> Self::Document
     where
         S: AsRef<str>;
+    /// Parses HTML as a fragment in `<body>`, without complementing `<html>` and `<body>` around it.
+    /// The root element of the document is the top-level element of the fragment if it's the only one,
+    /// otherwise a synthetic container whose children are the top-level nodes.
+    ///
+    /// By default, it's parsed as a whole document by `parse_document` instead, so the root is `<html>`
+    /// and the fragment is placed in the complemented `<body>`.
+    /// Then a field without `select` is extracted from `<html>`, not from the top-level element of the fragment
+    fn parse_fragment<S>(s: S) -> Self::Document
+    where
+        S: AsRef<str>,
+    {
+        Self::parse_document(s)
+    }
+    /// Parses HTML bytes, whose encoding is detected in the way of `encoding::decode`.
+    /// The detected encoding is returned together
+    fn parse_document_bytes(
+        bytes: &[u8],
+        hint: Option<&'static Encoding>,
+    ) -> (Self::Document, &'static Encoding) {
+        let (html, encoding) = encoding::decode(bytes, hint);
+        (Self::parse_document(html), encoding)
+    }
 }
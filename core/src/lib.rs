@@ -5,11 +5,16 @@ use std::error::Error;
 use std::fmt::Debug;
 
 use parseable::Parseable;
+use path::{ErrorPaths, PathSegment};
+use schema::StructSchema;
 
 use crate::functor::Functor;
 
+pub use encoding_rs;
+
 pub mod display;
 pub mod element_selector;
+pub mod encoding;
 pub mod error;
 pub mod extraction_method;
 pub mod field_value;
@@ -17,6 +22,9 @@ pub mod functor;
 pub mod html;
 pub mod macro_utils;
 pub mod parseable;
+pub mod path;
+pub mod schema;
+pub mod selector_syntax;
 pub mod transformable;
 pub mod traversable;
 pub mod traversable_with_context;
@@ -28,14 +36,96 @@ pub trait FromHtml: Sized {
     fn from_html<N>(input: N) -> Result<Self, Self::Error>
     where
         N: HtmlElement;
+
+    /// The paths to where the error occurred inside of this type, which are unknown by default
+    fn error_paths(_error: &Self::Error) -> Vec<Vec<PathSegment>> {
+        vec![vec![]]
+    }
+
+    /// The selectors of the fields, which are known only for the structs deriving `FromHtml`
+    fn schema() -> Option<StructSchema> {
+        None
+    }
 }
 
+/// An error of a field of `FromHtml`-deriving enum variant.
+/// A struct has its own error type generated by derive macro instead
 #[derive(Debug)]
 pub struct FieldError {
+    /// An empty name means that the error is propagated from a flattened field as it is
     pub field_name: String,
+    /// The paths to where the error occurred, including the field itself
+    pub paths: Vec<Vec<PathSegment>>,
     pub error: Box<dyn Error>,
 }
 
+impl ErrorPaths for FieldError {
+    fn paths(&self) -> Vec<Vec<PathSegment>> {
+        self.paths.clone()
+    }
+}
+
+impl<E> ErrorPaths for CollectedErrors<E>
+where
+    E: ErrorPaths,
+{
+    fn paths(&self) -> Vec<Vec<PathSegment>> {
+        self.errors.iter().flat_map(|e| e.paths()).collect()
+    }
+}
+
+impl ErrorPaths for VariantError {
+    /// The paths of the variants that failed to parse its fields.
+    /// It's empty path if no variant condition matched
+    fn paths(&self) -> Vec<Vec<PathSegment>> {
+        let paths = self
+            .rejected
+            .iter()
+            .flat_map(|r| match &r.reason {
+                RejectionReason::FieldError(e) => e.paths(),
+                RejectionReason::ConditionNotMatched { .. }
+                | RejectionReason::InvalidSelector(_) => vec![],
+            })
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            vec![vec![]]
+        } else {
+            paths
+        }
+    }
+}
+
+/// An error of `FromHtml`-deriving struct with `collect_errors`,
+/// which contains the errors of all the failed fields in declaration order.
+/// A `Vec` or an array field has an error for each failing item
+#[derive(Debug)]
+pub struct CollectedErrors<E> {
+    pub errors: Vec<E>,
+}
+
+/// An error of `FromHtml`-deriving enum, which is returned when no variant could be parsed
+#[derive(Debug)]
+pub struct VariantError {
+    /// Variants that were tried, in declaration order
+    pub rejected: Vec<RejectedVariant>,
+}
+
+#[derive(Debug)]
+pub struct RejectedVariant {
+    pub variant_name: String,
+    pub reason: RejectionReason,
+}
+
+#[derive(Debug)]
+pub enum RejectionReason {
+    /// The element doesn't match the `when` selector of the variant
+    ConditionNotMatched { selector: String },
+    /// The variant was selected, but one of its fields failed
+    FieldError(FieldError),
+    /// The backend failed to parse the `when` selector of the variant, so the following variants aren't tried
+    InvalidSelector(element_selector::InvalidSelector),
+}
+
 /// Similar with std::convert::Infallible
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Never {}
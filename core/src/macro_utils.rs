@@ -2,32 +2,38 @@
 //! These methods are shorthands to reduce codes in the `quote!` macro and improve development experience.
 //! If you are just a h2s user, you wouldn't call these methods directly.
 
-use crate::element_selector::TargetElementSelector;
-use crate::extraction_method::ExtractionMethod;
-use crate::field_value::FieldValue;
-use crate::html::HtmlElement;
+use crate::element_selector::{InvalidSelector, Select, SelectorCache, TargetElementSelector};
+use crate::extraction_method::{ExtractDefault, ExtractionMethod};
+use crate::field_value::{FieldValue, FieldValueOf};
+use crate::functor::{ExactlyOne, Functor};
+use crate::html::{HtmlElement, SourceSpan};
 use crate::parseable::{ExtractedValue, Parseable};
-use crate::transformable::TransformableFrom;
+use crate::path::PathSegment;
+use crate::transformable::{TransformableFrom, VecToSingleError};
 use crate::traversable::Traversable;
 use crate::traversable_with_context::{Context, FunctorWithContext};
+use crate::Never;
 use std::error::Error;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
 /// Process the source HTML element into the specified field value
 #[allow(clippy::type_complexity)]
-pub fn process_field<E, S, T, M, V, W, P, I>(
+pub fn process_field<E, S, T, M, V, W, P, I, R>(
     source_element: &E,
     target_element_selector: S,
     // By surrounding extraction method value with `ExtractionMethodWithType`, a caller of this
     // function can be empowered by type inference for a type of field value
-    ExtractionMethodWithType(extraction_method, _): ExtractionMethodWithType<V, M>,
+    extraction_method: ExtractionMethodWithType<V, M>,
+    error_mode: R,
 ) -> Result<
     V,
-    ProcessError<
-        TransformError<S, <W::Structure<E> as TransformableFrom<S::Output<E>>>::Error>,
-        ExtractionError<W::Context, M>,
-        ParseError<W::Context, P::Error>,
+    R::Errors<
+        ProcessError<
+            TransformError<S, <W::Structure<E> as TransformableFrom<S::Output<E>>>::Error>,
+            ExtractionError<W::Context, M>,
+            ParseError<W::Context, P::Error>,
+        >,
     >,
 >
 where
@@ -35,60 +41,487 @@ where
     S: TargetElementSelector<Output<E> = T>,
     W::Structure<E>: TransformableFrom<S::Output<E>>,
     M: ExtractionMethod<ExtractedValue<E> = I>,
-    V: FieldValue<Wrapped = W, Inner = P>,
+    V: FieldValue<Wrapped = W, Inner = P> + FieldValueOf<P, Wrapped = W>,
     P: Parseable<Input<E> = I>,
-    I: ExtractedValue,
     W: FunctorWithContext<Structure<P> = W, Inner = P> + Traversable,
+    R: ErrorMode<M>,
+{
+    process_field_with(
+        source_element,
+        target_element_selector,
+        extraction_method,
+        P::parse::<E>,
+        error_mode,
+    )
+}
+
+/// Same as `process_field`, but falls back to the default value when the target element or the value to extract is missing
+#[allow(clippy::type_complexity)]
+pub fn process_field_or_else<E, S, T, M, V, W, P, I, R, D>(
+    source_element: &E,
+    target_element_selector: S,
+    extraction_method: ExtractionMethodWithType<V, M>,
+    error_mode: R,
+    default: D,
+) -> Result<
+    V,
+    R::Errors<
+        ProcessError<
+            TransformError<S, <W::Structure<E> as TransformableFrom<S::Output<E>>>::Error>,
+            ExtractionError<W::Context, M>,
+            ParseError<W::Context, P::Error>,
+        >,
+    >,
+>
+where
+    E: HtmlElement,
+    S: TargetElementSelector<Output<E> = T>,
+    W::Structure<E>: TransformableFrom<S::Output<E>>,
+    M: ExtractionMethod<ExtractedValue<E> = I>,
+    V: FieldValue<Wrapped = W, Inner = P> + FieldValueOf<P, Wrapped = W>,
+    P: Parseable<Input<E> = I>,
+    W: FunctorWithContext<Structure<P> = W, Inner = P> + Traversable,
+    R: ErrorMode<M>,
+    D: FnOnce() -> V,
+{
+    process_field_with_or_else(
+        source_element,
+        target_element_selector,
+        extraction_method,
+        P::parse::<E>,
+        error_mode,
+        default,
+    )
+}
+
+/// Process the source HTML element into the specified field value, parsing each extracted value by the given function.
+/// Each item is extracted and parsed in order, and `error_mode` decides whether it stops at the first failing item
+#[allow(clippy::type_complexity)]
+pub fn process_field_with<E, S, T, M, V, W, P, I, F, PE, R>(
+    source_element: &E,
+    target_element_selector: S,
+    ExtractionMethodWithType(extraction_method, _): ExtractionMethodWithType<V, M>,
+    parse: F,
+    _error_mode: R,
+) -> Result<
+    V,
+    R::Errors<
+        ProcessError<
+            TransformError<S, <W::Structure<E> as TransformableFrom<S::Output<E>>>::Error>,
+            ExtractionError<W::Context, M>,
+            ParseError<W::Context, PE>,
+        >,
+    >,
+>
+where
+    E: HtmlElement,
+    S: TargetElementSelector<Output<E> = T>,
+    W::Structure<E>: TransformableFrom<S::Output<E>>,
+    M: ExtractionMethod<ExtractedValue<E> = I>,
+    V: FieldValueOf<P, Wrapped = W>,
+    W: FunctorWithContext<Structure<P> = W, Inner = P> + Traversable,
+    F: Fn(I) -> Result<P, PE>,
+    PE: Error,
+    R: ErrorMode<M>,
 {
-    let target_elements = target_element_selector.select(source_element);
+    let target_elements = target_element_selector
+        .select(source_element)
+        .map_err(ProcessError::InvalidSelector)
+        .map_err(R::single)?;
     let transformed = <_>::try_transform_from(target_elements)
         .map_err(|error| TransformError {
             selector: target_element_selector,
             error,
         })
-        .map_err(ProcessError::TransformError)?;
-    let with_context = W::fmap_with_context(transformed, |ctx, a: E| (ctx, a));
-    let extracted = W::traverse(with_context, |(ctx, a)| {
-        match extraction_method.extract(a) {
-            Ok(a) => Ok((ctx, a)),
-            Err(e) => Err((ctx, e)),
-        }
-    })
-    .map_err(|(ctx, e)| ExtractionError {
-        extraction_method,
-        context: ctx,
-        error: e,
-    })
-    .map_err(ProcessError::ExtractionError)?;
-    let parsed = W::traverse(extracted, |(ctx, a)| {
-        P::parse::<E>(a).map_err(|error| ParseError {
-            context: ctx,
-            error,
+        .map_err(ProcessError::TransformError)
+        .map_err(R::single)?;
+    let with_context = W::fmap_with_context(transformed, |ctx, element: E| (ctx, element));
+    let parsed = R::traverse::<W, _, _, _, _>(with_context, |(ctx, element)| {
+        // the element is kept to describe it only when an error occurs, since it's consumed by extraction
+        let extracted = match extraction_method.extract(element.clone()) {
+            Ok(extracted) => extracted,
+            Err(error) => return Err(ItemError::Extraction(ctx, element, error)),
+        };
+        parse(extracted).map_err(|error| {
+            ItemError::Parse(ParseError {
+                context: ctx,
+                span: element.source_span(),
+                snippet: element.error_snippet(),
+                error,
+            })
         })
     })
-    .map_err(ProcessError::ParseError)?;
+    .map_err(|errors| {
+        R::map(
+            errors,
+            extraction_method,
+            |extraction_method, error| match error {
+                ItemError::Extraction(ctx, element, error) => {
+                    ProcessError::ExtractionError(ExtractionError {
+                        extraction_method,
+                        context: ctx,
+                        span: element.source_span(),
+                        snippet: element.error_snippet(),
+                        error,
+                    })
+                }
+                ItemError::Parse(e) => ProcessError::ParseError(e),
+            },
+        )
+    })?;
     Ok(V::finalize(parsed))
 }
 
+/// Same as `process_field_with`, but falls back to the default value when the target element or the value to extract is missing.
+/// Only the first error decides it, even if all the errors are collected
+#[allow(clippy::type_complexity)]
+pub fn process_field_with_or_else<E, S, T, M, V, W, P, I, F, PE, R, D>(
+    source_element: &E,
+    target_element_selector: S,
+    extraction_method: ExtractionMethodWithType<V, M>,
+    parse: F,
+    error_mode: R,
+    default: D,
+) -> Result<
+    V,
+    R::Errors<
+        ProcessError<
+            TransformError<S, <W::Structure<E> as TransformableFrom<S::Output<E>>>::Error>,
+            ExtractionError<W::Context, M>,
+            ParseError<W::Context, PE>,
+        >,
+    >,
+>
+where
+    E: HtmlElement,
+    S: TargetElementSelector<Output<E> = T>,
+    W::Structure<E>: TransformableFrom<S::Output<E>>,
+    M: ExtractionMethod<ExtractedValue<E> = I>,
+    V: FieldValueOf<P, Wrapped = W>,
+    W: FunctorWithContext<Structure<P> = W, Inner = P> + Traversable,
+    F: Fn(I) -> Result<P, PE>,
+    PE: Error,
+    R: ErrorMode<M>,
+    D: FnOnce() -> V,
+{
+    let result = process_field_with(
+        source_element,
+        target_element_selector,
+        extraction_method,
+        parse,
+        error_mode,
+    );
+    let is_missing = match result.as_ref().map_err(R::first) {
+        Err(ProcessError::TransformError(e)) => {
+            <W::Structure<E> as TransformableFrom<S::Output<E>>>::is_missing(&e.error)
+        }
+        Err(ProcessError::ExtractionError(e)) => M::is_missing(&e.error),
+        _ => false,
+    };
+    if is_missing {
+        Ok(default())
+    } else {
+        result
+    }
+}
+
+/// The error of an item of a field, which is converted into `ProcessError` with the extraction method
+enum ItemError<C, E, ME, PE>
+where
+    C: Context,
+    PE: Error,
+{
+    Extraction(C, E, ME),
+    Parse(ParseError<C, PE>),
+}
+
+/// Whether processing a field stops at the first failing item, or goes on and reports all of them.
+/// It's generic over the extraction method `M`, which is given to each error
+pub trait ErrorMode<M> {
+    /// The error(s) of processing a field
+    type Errors<E>;
+
+    fn traverse<W, A, B, E, F>(
+        a: W::Structure<A>,
+        f: F,
+    ) -> Result<W::Structure<B>, Self::Errors<E>>
+    where
+        W: Traversable,
+        F: Fn(A) -> Result<B, E>;
+
+    /// Wraps the error that occurs before processing the items
+    fn single<E>(error: E) -> Self::Errors<E>;
+
+    /// Converts each error, giving the extraction method to it
+    fn map<A, B, F>(errors: Self::Errors<A>, extraction_method: M, f: F) -> Self::Errors<B>
+    where
+        F: Fn(M, A) -> B;
+
+    /// The first error, which decides whether the field value is missing
+    fn first<E>(errors: &Self::Errors<E>) -> &E;
+}
+
+/// Stops at the first failing item
+#[derive(Debug, Clone, Copy)]
+pub struct FirstError;
+
+impl<M> ErrorMode<M> for FirstError {
+    type Errors<E> = E;
+
+    fn traverse<W, A, B, E, F>(a: W::Structure<A>, f: F) -> Result<W::Structure<B>, Self::Errors<E>>
+    where
+        W: Traversable,
+        F: Fn(A) -> Result<B, E>,
+    {
+        W::traverse(a, f)
+    }
+
+    fn single<E>(error: E) -> Self::Errors<E> {
+        error
+    }
+
+    fn map<A, B, F>(error: Self::Errors<A>, extraction_method: M, f: F) -> Self::Errors<B>
+    where
+        F: Fn(M, A) -> B,
+    {
+        f(extraction_method, error)
+    }
+
+    fn first<E>(error: &Self::Errors<E>) -> &E {
+        error
+    }
+}
+
+/// Processes every item even if some of them fail, which is used by `collect_errors`.
+/// The extraction method is cloned for each error
+#[derive(Debug, Clone, Copy)]
+pub struct AllErrors;
+
+impl<M> ErrorMode<M> for AllErrors
+where
+    M: Clone,
+{
+    type Errors<E> = Vec<E>;
+
+    fn traverse<W, A, B, E, F>(a: W::Structure<A>, f: F) -> Result<W::Structure<B>, Self::Errors<E>>
+    where
+        W: Traversable,
+        F: Fn(A) -> Result<B, E>,
+    {
+        W::traverse_all(a, f)
+    }
+
+    fn single<E>(error: E) -> Self::Errors<E> {
+        vec![error]
+    }
+
+    fn map<A, B, F>(errors: Self::Errors<A>, extraction_method: M, f: F) -> Self::Errors<B>
+    where
+        F: Fn(M, A) -> B,
+    {
+        errors
+            .into_iter()
+            .map(|e| f(extraction_method.clone(), e))
+            .collect()
+    }
+
+    fn first<E>(errors: &Self::Errors<E>) -> &E {
+        errors.first().expect("an error occurred at least")
+    }
+}
+
+/// Moves the source HTML element to the single descendant selected by the struct-level `root` selector
+pub fn select_root<E>(source_element: &E, root_selector: Select) -> Result<E, SelectRootError>
+where
+    E: HtmlElement,
+{
+    let elements = root_selector
+        .select(source_element)
+        .map_err(ProcessError::InvalidSelector)?;
+    ExactlyOne::try_transform_from(elements)
+        .map(|ExactlyOne(e)| e)
+        .map_err(|error| {
+            ProcessError::TransformError(TransformError {
+                selector: root_selector,
+                error,
+            })
+        })
+}
+
+/// The error of `select_root`
+pub type SelectRootError = ProcessError<TransformError<Select, VecToSingleError>, Never, Never>;
+
+/// Whether the source HTML element satisfies the `when` condition of an enum variant
+pub fn matches_condition<E>(
+    source_element: &E,
+    condition: &'static SelectorCache,
+) -> Result<bool, InvalidSelector>
+where
+    E: HtmlElement,
+{
+    let selector = condition.get::<E::Selector>()?;
+    Ok(source_element.matches(&*selector))
+}
+
+/// Builds the paths of the error that occurred on processing a field,
+/// where `inner_paths` gives the paths inside of the parsed value
+pub fn field_error_paths<A, C, M, PE, F>(
+    field_name: &str,
+    selector: Option<&str>,
+    error: &ProcessError<A, ExtractionError<C, M>, ParseError<C, PE>>,
+    inner_paths: F,
+) -> Vec<Vec<PathSegment>>
+where
+    C: Context,
+    M: ExtractionMethod,
+    PE: Error,
+    F: FnOnce(&PE) -> Vec<Vec<PathSegment>>,
+{
+    let mut prefix = vec![PathSegment::Field {
+        name: field_name.to_string(),
+        selector: selector.map(str::to_string),
+    }];
+    let paths = match error {
+        ProcessError::InvalidSelector(_) | ProcessError::TransformError(_) => vec![vec![]],
+        ProcessError::ExtractionError(e) => {
+            prefix.extend(e.context.index().map(PathSegment::Index));
+            vec![vec![]]
+        }
+        ProcessError::ParseError(e) => {
+            prefix.extend(e.context.index().map(PathSegment::Index));
+            inner_paths(&e.error)
+        }
+    };
+    paths
+        .into_iter()
+        .map(|path| prefix.iter().cloned().chain(path).collect())
+        .collect()
+}
+
 pub struct ExtractionMethodWithType<V, E>(E, PhantomData<V>);
 
 pub fn extraction_method<V, E>(e: E) -> ExtractionMethodWithType<V, E> {
     ExtractionMethodWithType(e, PhantomData)
 }
 
-pub fn default_extraction_method<N: HtmlElement, V>(
-) -> ExtractionMethodWithType<V, <<V::Inner as Parseable>::Input<N> as ExtractedValue>::Default>
+/// The default extraction method for a field parsed by the given function, which is inferred from the argument type of the function.
+/// `source_element` only tells the type of HTML element
+pub fn parse_with_extraction_method<N, V, F, I, P, PE>(
+    _source_element: &N,
+    _parse: &F,
+) -> ExtractionMethodWithType<V, <I as ExtractedValue<N>>::Default>
+where
+    N: HtmlElement,
+    F: Fn(I) -> Result<P, PE>,
+    I: ExtractedValue<N>,
+{
+    ExtractionMethodWithType(I::default_method(), PhantomData)
+}
+
+pub fn default_extraction_method<V>() -> ExtractionMethodWithType<V, ExtractDefault<V::Inner>>
 where
     V: FieldValue,
 {
-    ExtractionMethodWithType(
-        <<V::Inner as Parseable>::Input<N> as ExtractedValue>::default_method(),
-        PhantomData,
-    )
+    ExtractionMethodWithType(ExtractDefault::new(), PhantomData)
+}
+
+/// The error type of `process_field` for the field value `V`.
+/// The unit type is used as a placeholder of HTML element, so that the type can be named
+/// in the error type generated by derive macro regardless of the HTML backend
+#[allow(clippy::type_complexity)]
+pub type FieldProcessError<V, S, M> = ProcessError<
+    TransformError<
+        S,
+        <<<V as FieldValue>::Wrapped as Functor>::Structure<()> as TransformableFrom<
+            <S as TargetElementSelector>::Output<()>,
+        >>::Error,
+    >,
+    ExtractionError<<<V as FieldValue>::Wrapped as FunctorWithContext>::Context, M>,
+    ParseError<
+        <<V as FieldValue>::Wrapped as FunctorWithContext>::Context,
+        <<V as FieldValue>::Inner as Parseable>::Error,
+    >,
+>;
+
+/// The error type of `process_field_with` for the field value `V` consisting of `P`
+#[allow(clippy::type_complexity)]
+pub type FieldProcessWithError<V, P, S, M> = ProcessError<
+    TransformError<
+        S,
+        <<<V as FieldValueOf<P>>::Wrapped as Functor>::Structure<()> as TransformableFrom<
+            <S as TargetElementSelector>::Output<()>,
+        >>::Error,
+    >,
+    ExtractionError<<<V as FieldValueOf<P>>::Wrapped as FunctorWithContext>::Context, M>,
+    ParseError<<<V as FieldValueOf<P>>::Wrapped as FunctorWithContext>::Context, ParseWithError>,
+>;
+
+/// Erases the type of the extraction method in the error of processing a field,
+/// so that the error of a field with `extractor` can be named in the error type generated by derive macro
+pub fn erase_extraction_method<A, C, M, P>(
+    error: ProcessError<A, ExtractionError<C, M>, P>,
+) -> ProcessError<A, ExtractionError<C, ErasedExtractionMethod>, P>
+where
+    C: Context,
+    M: ExtractionMethod,
+    M::Error: 'static,
+{
+    match error {
+        ProcessError::InvalidSelector(e) => ProcessError::InvalidSelector(e),
+        ProcessError::TransformError(e) => ProcessError::TransformError(e),
+        ProcessError::ExtractionError(e) => ProcessError::ExtractionError(ExtractionError {
+            context: e.context,
+            extraction_method: ErasedExtractionMethod(e.extraction_method.to_string()),
+            span: e.span,
+            snippet: e.snippet,
+            error: ExtractorError(Box::new(e.error)),
+        }),
+        ProcessError::ParseError(e) => ProcessError::ParseError(e),
+    }
+}
+
+/// The extraction method specified with `extractor` in errors, which keeps only its description.
+/// Since the type of the expression can't be named, the original error is boxed as `ExtractorError`
+#[derive(Debug, Clone)]
+pub struct ErasedExtractionMethod(pub String);
+
+impl ExtractionMethod for ErasedExtractionMethod {
+    type Error = ExtractorError;
+    type ExtractedValue<N: HtmlElement> = N;
+
+    /// It's only a placeholder of the original extraction method, so the element is passed through as it is
+    fn extract<N>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
+    where
+        N: HtmlElement,
+    {
+        Ok(element)
+    }
+}
+
+/// An error of an extraction method specified with `extractor`,
+/// whose original error can be retrieved by `downcast_ref`
+#[derive(Debug)]
+pub struct ExtractorError(pub Box<dyn Error>);
+
+/// An error returned by a function specified with `parse_with`.
+/// Since the type of the function can't be named, the original error is boxed,
+/// and can be retrieved by `downcast_ref`
+#[derive(Debug)]
+pub struct ParseWithError(pub Box<dyn Error>);
+
+impl ParseWithError {
+    pub fn new<E>(error: E) -> Self
+    where
+        E: Error + 'static,
+    {
+        Self(Box::new(error))
+    }
 }
 
 #[derive(Debug)]
 pub enum ProcessError<A, B, C> {
+    /// The backend failed to parse the css selector of the field
+    InvalidSelector(InvalidSelector),
     TransformError(A),
     ExtractionError(B),
     ParseError(C),
@@ -112,6 +545,10 @@ where
 {
     pub context: C,
     pub extraction_method: M,
+    /// The location of the element that the value was extracted from
+    pub span: Option<SourceSpan>,
+    /// The HTML of the element that the value was extracted from, if it's enabled
+    pub snippet: Option<String>,
     pub error: M::Error,
 }
 
@@ -122,5 +559,9 @@ where
     E: Error,
 {
     pub context: C,
+    /// The location of the element that the value was extracted from
+    pub span: Option<SourceSpan>,
+    /// The HTML of the element that the value was extracted from, if it's enabled
+    pub snippet: Option<String>,
     pub error: E,
 }
@@ -2,14 +2,20 @@
 
 use crate::extraction_method::{ExtractInnerText, ExtractionMethod, NoOp};
 use crate::html::HtmlElement;
-use crate::Error;
+use crate::path::PathSegment;
 use crate::FromHtml;
+use crate::{Error, Never};
 
 pub trait Parseable: Sized {
-    type Input<N: HtmlElement>: ExtractedValue;
+    type Input<N: HtmlElement>: ExtractedValue<N>;
     type Error: Error;
 
     fn parse<N: HtmlElement>(input: Self::Input<N>) -> Result<Self, Self::Error>;
+
+    /// The paths to where the error occurred inside of this type, which are unknown by default
+    fn error_paths(_error: &Self::Error) -> Vec<Vec<PathSegment>> {
+        vec![vec![]]
+    }
 }
 
 impl<T: FromHtml> Parseable for T {
@@ -19,6 +25,10 @@ impl<T: FromHtml> Parseable for T {
     fn parse<N: HtmlElement>(input: Self::Input<N>) -> Result<Self, Self::Error> {
         Self::from_html(input)
     }
+
+    fn error_paths(error: &Self::Error) -> Vec<Vec<PathSegment>> {
+        T::error_paths(error)
+    }
 }
 
 macro_rules! impl_parseable {
@@ -79,21 +89,29 @@ impl_parseable!(
     std::ffi::OsString
 );
 
-pub trait ExtractedValue {
-    type Default: ExtractionMethod;
+/// An error of `ParseableEnum`-deriving enum, which is returned when the value doesn't correspond to any variant
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnknownEnumValue {
+    pub value: String,
+    pub accepted: Vec<String>,
+}
+
+/// A value extracted from an HTML element of type `N`
+pub trait ExtractedValue<N: HtmlElement>: Sized {
+    /// The extraction method used when no extraction method is specified, which never fails
+    type Default: ExtractionMethod<ExtractedValue<N> = Self, Error = Never>;
     fn default_method() -> Self::Default;
 }
 
-impl<N: HtmlElement> ExtractedValue for N {
+impl<N: HtmlElement> ExtractedValue<N> for N {
     type Default = NoOp;
     fn default_method() -> Self::Default {
         NoOp
     }
 }
 
-impl ExtractedValue for String {
+impl<N: HtmlElement> ExtractedValue<N> for String {
     type Default = ExtractInnerText;
-
     fn default_method() -> Self::Default {
         ExtractInnerText
     }
@@ -0,0 +1,20 @@
+//! Paths to where errors occurred, which are available as data in addition to the error messages
+
+/// A segment of the path from the root struct to where an error occurred
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum PathSegment {
+    /// A field of struct or enum variant, with the css selector to select its element if specified
+    Field {
+        name: String,
+        selector: Option<String>,
+    },
+    /// An index of the element in `Vec` or array
+    Index(usize),
+}
+
+/// An error that knows the paths to where it occurred.
+/// An error can have multiple paths, e.g. a struct with `collect_errors` or an untagged enum.
+/// An empty path means that the error occurred on the element itself
+pub trait ErrorPaths {
+    fn paths(&self) -> Vec<Vec<PathSegment>>;
+}
@@ -0,0 +1,29 @@
+//! A static description of the structs deriving `FromHtml`,
+//! for the consumers that need to know the selectors without parsing, like streaming.
+
+/// The struct-level attributes and the fields of a struct
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StructSchema {
+    /// The selector of the struct-level `root` attribute
+    pub root: Option<&'static str>,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl StructSchema {
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldSchema {
+    /// The field name, or the index for a tuple struct
+    pub name: &'static str,
+    /// `None` if the field is extracted from the element itself, or flattened
+    pub selector: Option<&'static str>,
+    /// Whether the field value is a `Vec`, which takes all of the selected elements
+    pub is_vec: bool,
+    /// The name of the type parsed from each selected element, e.g. `T` for `Vec<T>`, `Option<T>` and `[T; N]`,
+    /// as given by `std::any::type_name`, which doesn't require the type to be `'static`
+    pub item_type: &'static str,
+}
@@ -0,0 +1,475 @@
+//! A backend-independent validator of CSS selector syntax.
+//! It's used to check selectors at compile time, so it only checks the grammar of
+//! [Selectors Level 4](https://www.w3.org/TR/selectors-4/), not whether a backend supports each pseudo-class.
+
+use std::fmt::{Display, Formatter};
+
+/// Checks whether the given string is a syntactically valid selector list
+pub fn validate(selector: &str) -> Result<(), SelectorSyntaxError> {
+    let mut parser = Parser {
+        input: selector,
+        pos: 0,
+    };
+    parser.selector_list(false)?;
+    match parser.peek() {
+        None => Ok(()),
+        Some(_) => Err(parser.error("unexpected character")),
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SelectorSyntaxError {
+    /// The byte offset in the selector where the error is detected
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl Display for SelectorSyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for SelectorSyntaxError {}
+
+/// Pseudo-classes whose argument is a selector list
+const SELECTOR_LIST_PSEUDO_CLASSES: [&str; 4] = ["not", "is", "where", "matches"];
+/// Pseudo-classes whose argument is `An+B`, optionally followed by `of <selector list>`
+const NTH_PSEUDO_CLASSES: [&str; 4] = [
+    "nth-child",
+    "nth-last-child",
+    "nth-of-type",
+    "nth-last-of-type",
+];
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.input[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &'static str) -> SelectorSyntaxError {
+        SelectorSyntaxError {
+            position: self.pos,
+            message,
+        }
+    }
+
+    /// Skips whitespaces and returns whether any whitespace is skipped
+    fn whitespaces(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r' | '\x0c')) {
+            self.bump();
+        }
+        self.pos > start
+    }
+
+    /// `relative` allows a leading combinator like `:has(> a)`
+    fn selector_list(&mut self, relative: bool) -> Result<(), SelectorSyntaxError> {
+        loop {
+            self.whitespaces();
+            self.complex_selector(relative)?;
+            self.whitespaces();
+            if !self.eat(',') {
+                return Ok(());
+            }
+        }
+    }
+
+    fn complex_selector(&mut self, relative: bool) -> Result<(), SelectorSyntaxError> {
+        if !(relative && self.combinator()) {
+            self.compound_selector()?;
+        } else {
+            self.whitespaces();
+            self.compound_selector()?;
+        }
+        loop {
+            let start = self.pos;
+            let has_whitespace = self.whitespaces();
+            if self.combinator() {
+                self.whitespaces();
+                self.compound_selector()?;
+            } else if has_whitespace && self.starts_compound_selector() {
+                // descendant combinator
+                self.compound_selector()?;
+            } else {
+                self.pos = start;
+                return Ok(());
+            }
+        }
+    }
+
+    fn combinator(&mut self) -> bool {
+        self.eat('>') || self.eat('+') || self.eat('~')
+    }
+
+    fn starts_compound_selector(&self) -> bool {
+        match self.peek() {
+            Some('*' | '|' | '#' | '.' | '[' | ':' | '\\') => true,
+            Some(c) => is_name_start(c) || c == '-',
+            None => false,
+        }
+    }
+
+    fn compound_selector(&mut self) -> Result<(), SelectorSyntaxError> {
+        let start = self.pos;
+        self.type_selector()?;
+        let mut pseudo_element = false;
+        loop {
+            match self.peek() {
+                Some('#') if !pseudo_element => {
+                    self.bump();
+                    self.ident().ok_or_else(|| self.error("expected id"))?;
+                }
+                Some('.') if !pseudo_element => {
+                    self.bump();
+                    self.ident()
+                        .ok_or_else(|| self.error("expected class name"))?;
+                }
+                Some('[') if !pseudo_element => self.attribute()?,
+                Some(':') => {
+                    self.bump();
+                    if self.eat(':') {
+                        pseudo_element = true;
+                    }
+                    self.pseudo()?;
+                }
+                _ => break,
+            }
+        }
+        if self.pos == start {
+            return Err(self.error("expected selector"));
+        }
+        Ok(())
+    }
+
+    /// Parses an optional type selector with namespace prefix, like `div`, `*`, `svg|a` or `*|*`
+    fn type_selector(&mut self) -> Result<(), SelectorSyntaxError> {
+        let start = self.pos;
+        let name = self.eat('*') || self.ident().is_some();
+        if self.peek() == Some('|') && self.peek_nth(1) != Some('=') {
+            self.bump();
+            if !(self.eat('*') || self.ident().is_some()) {
+                return Err(self.error("expected element name after namespace"));
+            }
+        } else if !name {
+            self.pos = start;
+        }
+        Ok(())
+    }
+
+    fn attribute(&mut self) -> Result<(), SelectorSyntaxError> {
+        self.bump(); // [
+        self.whitespaces();
+        // namespace prefix
+        if self.peek() == Some('*') || (self.peek() == Some('|') && self.peek_nth(1) != Some('=')) {
+            self.eat('*');
+            if !self.eat('|') {
+                return Err(self.error("expected `|`"));
+            }
+        }
+        self.ident()
+            .ok_or_else(|| self.error("expected attribute name"))?;
+        if self.peek() == Some('|') && self.peek_nth(1) != Some('=') {
+            self.bump();
+            self.ident()
+                .ok_or_else(|| self.error("expected attribute name"))?;
+        }
+        self.whitespaces();
+        if self.eat(']') {
+            return Ok(());
+        }
+        match self.bump() {
+            Some('=') => {}
+            Some('~' | '|' | '^' | '$' | '*') if self.eat('=') => {}
+            _ => return Err(self.error("expected attribute matcher")),
+        }
+        self.whitespaces();
+        if matches!(self.peek(), Some('"' | '\'')) {
+            self.string()?;
+        } else if self.ident().is_none() {
+            return Err(self.error("expected attribute value"));
+        }
+        self.whitespaces();
+        let modifier_start = self.pos;
+        if let Some(modifier) = self.ident() {
+            if !matches!(modifier.to_ascii_lowercase().as_str(), "i" | "s") {
+                self.pos = modifier_start;
+                return Err(self.error("unknown attribute modifier"));
+            }
+            self.whitespaces();
+        }
+        if !self.eat(']') {
+            return Err(self.error("expected `]`"));
+        }
+        Ok(())
+    }
+
+    /// Parses the name and the arguments of a pseudo-class or a pseudo-element after colons
+    fn pseudo(&mut self) -> Result<(), SelectorSyntaxError> {
+        let name = self
+            .ident()
+            .ok_or_else(|| self.error("expected pseudo-class name"))?
+            .to_ascii_lowercase();
+        if !self.eat('(') {
+            return Ok(());
+        }
+        self.whitespaces();
+        if SELECTOR_LIST_PSEUDO_CLASSES.contains(&name.as_str()) {
+            self.selector_list(false)?;
+        } else if name == "has" {
+            self.selector_list(true)?;
+        } else if NTH_PSEUDO_CLASSES.contains(&name.as_str()) {
+            self.an_plus_b()?;
+            let start = self.pos;
+            if self.whitespaces()
+                && matches!(self.ident(), Some(of) if of.eq_ignore_ascii_case("of"))
+            {
+                self.selector_list(false)?;
+            } else {
+                self.pos = start;
+            }
+        } else {
+            self.any_arguments()?;
+        }
+        self.whitespaces();
+        if !self.eat(')') {
+            return Err(self.error("expected `)`"));
+        }
+        Ok(())
+    }
+
+    /// Parses `An+B` notation like `odd`, `2n+1`, `-n + 3` or `5`
+    fn an_plus_b(&mut self) -> Result<(), SelectorSyntaxError> {
+        let start = self.pos;
+        if let Some(keyword) = self.ident() {
+            if keyword.eq_ignore_ascii_case("odd") || keyword.eq_ignore_ascii_case("even") {
+                return Ok(());
+            }
+            self.pos = start;
+        }
+        if !self.eat('+') {
+            self.eat('-');
+        }
+        let has_a = self.digits();
+        if self.eat('n') || self.eat('N') {
+            let before_b = self.pos;
+            self.whitespaces();
+            if self.eat('+') || self.eat('-') {
+                self.whitespaces();
+                if !self.digits() {
+                    return Err(self.error("expected number"));
+                }
+            } else {
+                self.pos = before_b;
+            }
+        } else if !has_a {
+            return Err(self.error("expected `An+B` notation"));
+        }
+        Ok(())
+    }
+
+    /// Skips the arguments of an unknown functional pseudo-class, keeping brackets balanced
+    fn any_arguments(&mut self) -> Result<(), SelectorSyntaxError> {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None => return Err(self.error("expected `)`")),
+                Some(')') if depth == 0 => return Ok(()),
+                Some('(') => depth += 1,
+                Some(')') => depth -= 1,
+                Some('"' | '\'') => {
+                    self.string()?;
+                    continue;
+                }
+                Some('\\') => {
+                    self.escape()?;
+                    continue;
+                }
+                _ => {}
+            }
+            self.bump();
+        }
+    }
+
+    fn digits(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.bump();
+        }
+        self.pos > start
+    }
+
+    fn string(&mut self) -> Result<(), SelectorSyntaxError> {
+        let quote = self.bump();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\\') => {
+                    self.bump();
+                    // an escaped newline is a line continuation
+                    if self.bump().is_none() {
+                        return Err(self.error("unterminated string"));
+                    }
+                }
+                c if c == quote => {
+                    self.bump();
+                    return Ok(());
+                }
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    /// Parses an identifier, and returns it without unescaping
+    fn ident(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        let valid_start = match (self.peek(), self.peek_nth(1), self.peek_nth(2)) {
+            (Some('-'), Some('-'), _) => true,
+            (Some('-'), Some('\\'), Some(c)) => c != '\n',
+            (Some('-'), Some(c), _) => is_name_start(c),
+            (Some('\\'), Some(c), _) => c != '\n',
+            (Some(c), ..) => is_name_start(c),
+            _ => false,
+        };
+        if !valid_start {
+            return None;
+        }
+        loop {
+            match self.peek() {
+                Some('\\') => {
+                    if self.escape().is_err() {
+                        self.pos = start;
+                        return None;
+                    }
+                }
+                Some(c) if is_name(c) => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+        Some(&self.input[start..self.pos])
+    }
+
+    fn escape(&mut self) -> Result<(), SelectorSyntaxError> {
+        self.bump(); // backslash
+        match self.bump() {
+            None | Some('\n') => Err(self.error("invalid escape")),
+            Some(c) if c.is_ascii_hexdigit() => {
+                let mut len = 1;
+                while len < 6 && matches!(self.peek(), Some(c) if c.is_ascii_hexdigit()) {
+                    self.bump();
+                    len += 1;
+                }
+                self.eat(' ');
+                Ok(())
+            }
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        for selector in [
+            "div",
+            "*",
+            ".a",
+            "#main",
+            "div.a.b#c",
+            "div > .a",
+            "div>.a",
+            "ul li + li ~ li",
+            "  a , b  ",
+            "a[href]",
+            "a[ href ^= \"https://\" i ]",
+            "meta[property='og:image']",
+            "[data-id=foo]",
+            "svg|rect",
+            "*|*",
+            "[xlink|href]",
+            "li:first-child",
+            "li:nth-child(2n+1)",
+            "li:nth-child( -n + 3 )",
+            "li:nth-of-type(odd)",
+            "li:nth-child(2 of .item)",
+            "p:not(.a, .b)",
+            ":is(h1, h2) > span",
+            "div:has(> img)",
+            "p::before",
+            "p:lang(en)",
+            ".\\31 23",
+            "#\\#id",
+            "-foo",
+            "--bar",
+            ".日本語",
+        ] {
+            assert_eq!(validate(selector), Ok(()), "{selector}");
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        for (selector, position) in [
+            ("", 0),
+            (":invalid:", 9),
+            ("div >", 5),
+            ("div,", 4),
+            (".", 1),
+            ("#1a", 1),
+            ("a[href", 6),
+            ("a[href=]", 7),
+            ("a[href=\"foo]", 12),
+            ("a[href=foo x]", 11),
+            ("li:nth-child(x)", 13),
+            ("p:not(.a", 8),
+            ("div)", 3),
+            ("> a", 0),
+            ("a b >> c", 5),
+        ] {
+            assert_eq!(
+                validate(selector).map_err(|e| e.position),
+                Err(position),
+                "{selector}"
+            );
+        }
+    }
+}
@@ -6,6 +6,12 @@ use crate::Never;
 pub trait TransformableFrom<T>: Sized {
     type Error: Error;
     fn try_transform_from(t: T) -> Result<Self, Self::Error>;
+
+    /// Whether the error means that no source element exists.
+    /// A field with `default` attribute falls back to the default value on such error.
+    fn is_missing(_error: &Self::Error) -> bool {
+        false
+    }
 }
 
 impl<T> TransformableFrom<T> for T {
@@ -26,6 +32,10 @@ impl<T> TransformableFrom<Vec<T>> for ExactlyOne<T> {
             t.pop().map(ExactlyOne).ok_or(VecToSingleError::NoElements)
         }
     }
+
+    fn is_missing(error: &Self::Error) -> bool {
+        matches!(error, VecToSingleError::NoElements)
+    }
 }
 
 impl<N, const A: usize> TransformableFrom<Vec<N>> for [N; A] {
@@ -95,6 +105,16 @@ mod test {
         );
     }
 
+    #[test]
+    fn vec_to_single_missing() {
+        assert!(<ExactlyOne<()> as TransformableFrom<Vec<()>>>::is_missing(
+            &VecToSingleError::NoElements
+        ));
+        assert!(!<ExactlyOne<()> as TransformableFrom<Vec<()>>>::is_missing(
+            &VecToSingleError::TooManyElements { found: 2 }
+        ));
+    }
+
     #[test]
     fn vec_to_array() {
         assert_eq!(
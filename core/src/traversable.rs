@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use crate::functor::{ExactlyOne, Functor};
 use crate::Tuple;
 
@@ -7,6 +9,20 @@ pub trait Traversable: Functor {
     fn traverse<A, B, E, F>(a: Self::Structure<A>, f: F) -> Result<Self::Structure<B>, E>
     where
         F: Fn(A) -> Result<B, E>;
+
+    /// Same as `traverse`, but `f` is applied to every item even if some of them fail, and all the errors are returned
+    fn traverse_all<A, B, E, F>(a: Self::Structure<A>, f: F) -> Result<Self::Structure<B>, Vec<E>>
+    where
+        F: Fn(A) -> Result<B, E>,
+    {
+        let errors = RefCell::new(vec![]);
+        let results = Self::fmap(a, |a| f(a).map_err(|e| errors.borrow_mut().push(e)).ok());
+        let errors = errors.into_inner();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Self::traverse(results, |b| b.ok_or(())).expect("every item has succeeded"))
+    }
 }
 
 impl<T> Traversable for ExactlyOne<T> {
@@ -112,6 +128,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn traverse_all() {
+        assert_eq!(
+            Vec::<()>::traverse_all(vec!["a", "bb"], try_func),
+            Ok(vec![1, 2])
+        );
+        assert_eq!(
+            Vec::<()>::traverse_all(vec!["!a", "b", "!c"], try_func),
+            Err(vec![ErrorImpl("!a"), ErrorImpl("!c")]),
+            "all the errors are returned in order"
+        );
+        assert_eq!(
+            Option::<()>::traverse_all(Some("!a"), try_func),
+            Err(vec![ErrorImpl("!a")])
+        );
+    }
+
     #[test]
     fn array() {
         assert_eq!(
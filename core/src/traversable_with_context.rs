@@ -64,7 +64,12 @@ impl<T, const M: usize> FunctorWithContext for [T; M] {
     }
 }
 
-pub trait Context: Debug + Display {}
+pub trait Context: Debug + Display {
+    /// The index of the element in the list, if the context is about a list
+    fn index(&self) -> Option<usize> {
+        None
+    }
+}
 
 #[derive(Debug)]
 pub struct NoContext;
@@ -74,4 +79,8 @@ impl Context for NoContext {}
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ListIndex(pub usize);
 
-impl Context for ListIndex {}
+impl Context for ListIndex {
+    fn index(&self) -> Option<usize> {
+        Some(self.0)
+    }
+}
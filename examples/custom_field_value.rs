@@ -19,17 +19,25 @@ fn main() {
         }
     }
 
+    // Or you can specify a function to parse the value without defining a newtype
+    fn parse_duration(input: String) -> Result<std::time::Duration, ParseIntError> {
+        Ok(std::time::Duration::from_secs(input.parse()?))
+    }
+
     #[derive(FromHtml)]
     struct MyStruct {
         #[h2s(select = "div")]
         duration1: Duration,
         #[h2s(select = "div", attr = "seconds")]
         duration2: Duration,
+        #[h2s(select = "div", parse_with = "parse_duration")]
+        duration3: std::time::Duration,
     }
 
     let my_struct = h2s::parse::<MyStruct>(r#"<div seconds="456">123</div>"#).unwrap();
     assert_eq!(my_struct.duration1.0, std::time::Duration::from_secs(123));
     assert_eq!(my_struct.duration2.0, std::time::Duration::from_secs(456));
+    assert_eq!(my_struct.duration3, std::time::Duration::from_secs(123));
 }
 
 #[test]
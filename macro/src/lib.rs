@@ -3,13 +3,20 @@
 
 use proc_macro::TokenStream;
 
-use darling::ast::Data;
-use darling::{FromDeriveInput, FromField};
-use quote::{quote, ToTokens};
-use scraper::Selector;
+use darling::ast::{Data, Fields};
+use darling::util::{Override, SpannedValue};
+use darling::{FromDeriveInput, FromField, FromVariant};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Expr};
+use syn::{parse_macro_input, parse_quote, Expr};
 
+/// Derives `FromHtml` for a struct or an enum.
+///
+/// For a struct, an error enum named `{Struct}Error` is generated next to it, which has a variant for each field.
+/// The variant is named in UpperCamelCase of the field name, e.g. `BlogTitle` for `blog_title` and `Field0` for the first field of tuple struct.
+/// They can be renamed by `#[h2s(error = "...")]` on the struct and on the field respectively,
+/// e.g. when the name of the error type is already used, or the variants of two fields conflict like `a_1` and `a1`.
 #[proc_macro_derive(FromHtml, attributes(h2s))]
 pub fn derive(input: TokenStream) -> TokenStream {
     match FromHtmlStructReceiver::from_derive_input(&parse_macro_input!(input)) {
@@ -18,11 +25,43 @@ pub fn derive(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(ParseableEnum, attributes(h2s))]
+pub fn derive_parseable_enum(input: TokenStream) -> TokenStream {
+    match ParseableEnumReceiver::from_derive_input(&parse_macro_input!(input)) {
+        Ok(enum_receiver) => quote!(#enum_receiver).into(),
+        Err(e) => TokenStream::from(e.write_errors()),
+    }
+}
+
 #[derive(Debug, FromDeriveInput)]
-#[darling(attributes(h2s), supports(struct_any))]
+#[darling(attributes(h2s), supports(struct_any, enum_any))]
 struct FromHtmlStructReceiver {
     ident: syn::Ident,
-    data: Data<(), H2sFieldReceiver>,
+    vis: syn::Visibility,
+    generics: syn::Generics,
+    data: Data<H2sVariantReceiver, H2sFieldReceiver>,
+
+    /// Tries each variant in declaration order instead of selecting it by `when` condition
+    #[darling(default)]
+    untagged: bool,
+    /// A css selector to move the source element to its single descendant before processing fields
+    root: Option<SpannedValue<String>>,
+    /// Processes every field even if some of them fail, and returns all the errors.
+    /// Every item of a `Vec` or an array field is processed too, so an `extractor` must be `Clone`
+    #[darling(default)]
+    collect_errors: bool,
+    /// The name of the generated error type, which is `{Struct}Error` by default
+    error: Option<syn::Ident>,
+}
+
+#[derive(Debug, FromVariant)]
+#[darling(attributes(h2s))]
+struct H2sVariantReceiver {
+    ident: syn::Ident,
+    fields: Fields<H2sFieldReceiver>,
+
+    /// A css selector that the source element should match to parse as this variant
+    when: Option<SpannedValue<String>>,
 }
 
 #[derive(Debug, FromField)]
@@ -31,104 +70,985 @@ struct H2sFieldReceiver {
     ident: Option<syn::Ident>,
     ty: syn::Type,
 
-    select: Option<String>,
+    select: Option<SpannedValue<String>>,
     extractor: Option<Expr>,
     // TODO attr is a shorthand of specific extractor
     //      so it's better to represent that user cannot specify both
     attr: Option<String>,
-    // text: bool,
+    /// A shorthand of `extractor = ExtractOwnText`, which takes only the direct text children
+    #[darling(default)]
+    own_text: bool,
+    /// A shorthand of `extractor = ExtractInnerHtml`
+    #[darling(default)]
+    inner_html: bool,
+    /// A shorthand of `extractor = ExtractOuterHtml`
+    #[darling(default)]
+    outer_html: bool,
+    /// Falls back to `Default::default()` or the specified function when the value is missing
+    default: Option<Override<syn::Path>>,
+    /// Parses the parent's element as the nested struct, and inlines its fields into the parent
+    #[darling(default)]
+    flatten: bool,
+    /// A function to parse the extracted value instead of `Parseable`.
+    /// Unless specified, the extraction method is the default one of the argument type (`ExtractedValue::default_method`).
+    /// A function that receives the element itself should be generic over `HtmlElement`,
+    /// and be used with `extractor = NoOp`
+    parse_with: Option<syn::Path>,
+    /// The name of the variant of the generated error type for this field
+    error: Option<syn::Ident>,
 }
 
 impl ToTokens for FromHtmlStructReceiver {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let Self {
             ref ident,
+            ref generics,
             ref data,
+            untagged,
+            ref root,
+            collect_errors,
+            ..
         } = *self;
 
+        let fields: Vec<&H2sFieldReceiver> = match data {
+            Data::Struct(fields) => fields.iter().collect(),
+            Data::Enum(variants) => variants.iter().flat_map(|v| v.fields.iter()).collect(),
+        };
+        let generics = build_generics(generics, &fields);
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
         let token_stream = match data.as_ref() {
-            Data::Struct(fields) => {
-                let field_and_values = fields
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, r)| r.build_field_and_value(i));
-                // TODO Avoid using trait object
-                quote! {
-                    impl ::h2s::FromHtml for #ident {
-                        type Error = ::h2s::FieldError;
-
-                        fn from_html<E>(input: E) -> Result<Self, Self::Error>
-                        where
-                            E: ::h2s::html::HtmlElement
-                        {
-                            Ok(Self{
-                                #(#field_and_values),*
-                            })
+            Data::Struct(_) if untagged => {
+                syn::Error::new(ident.span(), "`untagged` is only available for enum")
+                    .to_compile_error()
+            }
+            Data::Enum(_) if root.is_some() => {
+                syn::Error::new(ident.span(), "`root` is only available for struct")
+                    .to_compile_error()
+            }
+            Data::Enum(_) if collect_errors => syn::Error::new(
+                ident.span(),
+                "`collect_errors` is only available for struct",
+            )
+            .to_compile_error(),
+            Data::Enum(_) if self.error.is_some() => {
+                syn::Error::new(ident.span(), "`error` is only available for struct")
+                    .to_compile_error()
+            }
+            Data::Struct(fields) => self.build_struct(fields.fields, &generics),
+            Data::Enum(variants) => {
+                let invalid_variant = if untagged {
+                    variants
+                        .iter()
+                        .find(|v| v.when.is_some())
+                        .map(|v| (v, "`when` condition can't be used with untagged enum"))
+                } else {
+                    variants
+                        .iter()
+                        .rev()
+                        .skip(1)
+                        .find(|v| v.when.is_none())
+                        .map(|v| (v, "only the last variant can omit `when` condition"))
+                };
+                match invalid_variant {
+                    Some((v, message)) => {
+                        syn::Error::new(v.ident.span(), message).to_compile_error()
+                    }
+                    None => {
+                        let body = variants.into_iter().rev().fold(
+                            quote!(Err(::h2s::VariantError { rejected })),
+                            |otherwise, v| {
+                                if untagged {
+                                    v.build_untagged_variant(otherwise)
+                                } else {
+                                    v.build_variant(otherwise)
+                                }
+                            },
+                        );
+                        quote! {
+                            impl #impl_generics ::h2s::FromHtml for #ident #ty_generics #where_clause {
+                                type Error = ::h2s::VariantError;
+
+                                fn from_html<E>(input: E) -> Result<Self, Self::Error>
+                                where
+                                    E: ::h2s::html::HtmlElement
+                                {
+                                    let mut rejected = vec![];
+                                    #body
+                                }
+
+                                fn error_paths(error: &Self::Error) -> Vec<Vec<::h2s::path::PathSegment>> {
+                                    ::h2s::path::ErrorPaths::paths(error)
+                                }
+                            }
                         }
                     }
                 }
             }
-            Data::Enum(_) => {
-                syn::Error::new(ident.span(), "FromHtml doesn't support enum".to_string())
-                    .to_compile_error()
-            }
         };
 
         tokens.extend(token_stream);
     }
 }
 
+impl FromHtmlStructReceiver {
+    /// Builds the `FromHtml` implementation of the struct, and its error type
+    /// that has a variant for each field
+    fn build_struct(
+        &self,
+        fields: Vec<&H2sFieldReceiver>,
+        generics: &syn::Generics,
+    ) -> proc_macro2::TokenStream {
+        let Self {
+            ref ident,
+            ref vis,
+            ref root,
+            collect_errors,
+            ..
+        } = *self;
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let error_ident = self
+            .error
+            .clone()
+            .unwrap_or_else(|| format_ident!("{}Error", ident));
+
+        // (variant, field name to display, error type, doc)
+        let mut error_variants = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let field_name = field.field_name(i);
+                let doc = if field.flatten {
+                    format!("An error of the flattened field `{field_name}`")
+                } else {
+                    format!("An error of the field `{field_name}`")
+                };
+                (
+                    field.error_variant_ident(i),
+                    (!field.flatten).then_some(field_name),
+                    field.build_error_type(),
+                    doc,
+                )
+            })
+            .collect::<Vec<_>>();
+        for (i, (variant, ..)) in error_variants.iter().enumerate() {
+            if let Some(j) = error_variants[..i].iter().position(|(v, ..)| v == variant) {
+                return syn::Error::new(
+                    variant.span(),
+                    format!(
+                        "the error variant `{variant}` of this field conflicts with the one of the field `{}`; \
+                         rename it by `#[h2s(error = \"...\")]`",
+                        fields[j].field_name(j)
+                    ),
+                )
+                .to_compile_error();
+            }
+        }
+        let select_root = match root {
+            Some(selector) => {
+                let root_variant = format_ident!("Root");
+                if let Some((v, ..)) = error_variants.iter().find(|(v, ..)| *v == root_variant) {
+                    return syn::Error::new(
+                        v.span(),
+                        "the error variant of this field conflicts with the one of `root`; \
+                         rename it by `#[h2s(error = \"...\")]`",
+                    )
+                    .to_compile_error();
+                }
+                error_variants.push((
+                    root_variant,
+                    None,
+                    quote!(::h2s::macro_utils::SelectRootError),
+                    "The element selected by `root` isn't exactly one".to_string(),
+                ));
+                Some(build_select_root(
+                    selector,
+                    selector.span(),
+                    &error_ident,
+                    collect_errors,
+                ))
+            }
+            None => None,
+        };
+
+        let (error_type, body) = if collect_errors {
+            (
+                quote!(::h2s::CollectedErrors<#error_ident #ty_generics>),
+                build_collecting_struct_value(fields.clone(), &error_ident, &ty_generics),
+            )
+        } else {
+            let value = build_struct_value(quote!(Self), fields.clone(), Some(&error_ident));
+            (quote!(#error_ident #ty_generics), quote!(Ok(#value)))
+        };
+
+        let error_types = error_variants.iter().map(|(_, _, ty, _)| ty);
+        let mut error_generics = generics.clone();
+        error_generics.make_where_clause().predicates.extend(
+            error_types.clone().map::<syn::WherePredicate, _>(
+                |ty| parse_quote!(#ty: ::std::fmt::Debug + ::std::fmt::Display),
+            ),
+        );
+        let error_where_clause = &error_generics.where_clause;
+
+        let doc = format!(
+            "An error of parsing [`{ident}`] from HTML, which is caused by one of the fields"
+        );
+        let variant_docs = error_variants.iter().map(|(.., doc)| doc);
+        let path_arms = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let variant = field.error_variant_ident(i);
+                let paths = field.build_error_paths(i, quote!(e));
+                quote!(Self::#variant(ref e) => #paths)
+            })
+            .chain(root.as_ref().map(|_| quote!(Self::Root(_) => vec![vec![]])));
+        let root_schema = match root {
+            Some(selector) => {
+                let selector = selector.as_str();
+                quote!(Some(#selector))
+            }
+            None => quote!(None),
+        };
+        let field_schemas = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| field.build_schema(i));
+        let variant_idents = error_variants.iter().map(|(v, ..)| v).collect::<Vec<_>>();
+        let variant_names = variant_idents.iter().map(|v| v.to_string());
+        let display_arms = error_variants.iter().map(|(v, name, ..)| match name {
+            Some(name) => quote!(Self::#v(ref e) => write!(f, "{}: {}", #name, e)),
+            None => quote!(Self::#v(ref e) => write!(f, "{}", e)),
+        });
+
+        quote! {
+            #[doc = #doc]
+            #vis enum #error_ident #generics #where_clause {
+                #(
+                    #[doc = #variant_docs]
+                    #variant_idents(#error_types),
+                )*
+            }
+
+            impl #impl_generics ::std::fmt::Debug for #error_ident #ty_generics #error_where_clause {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    match *self {
+                        #(Self::#variant_idents(ref e) => f.debug_tuple(#variant_names).field(e).finish(),)*
+                    }
+                }
+            }
+
+            impl #impl_generics ::std::fmt::Display for #error_ident #ty_generics #error_where_clause {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    match *self {
+                        #(#display_arms,)*
+                    }
+                }
+            }
+
+            impl #impl_generics ::std::error::Error for #error_ident #ty_generics #error_where_clause {}
+
+            impl #impl_generics ::h2s::path::ErrorPaths for #error_ident #ty_generics #where_clause {
+                fn paths(&self) -> Vec<Vec<::h2s::path::PathSegment>> {
+                    match *self {
+                        #(#path_arms,)*
+                    }
+                }
+            }
+
+            impl #impl_generics ::h2s::FromHtml for #ident #ty_generics #where_clause {
+                type Error = #error_type;
+
+                fn from_html<E>(input: E) -> Result<Self, Self::Error>
+                where
+                    E: ::h2s::html::HtmlElement
+                {
+                    #select_root
+                    #body
+                }
+
+                fn error_paths(error: &Self::Error) -> Vec<Vec<::h2s::path::PathSegment>> {
+                    ::h2s::path::ErrorPaths::paths(error)
+                }
+
+                fn schema() -> Option<::h2s::schema::StructSchema> {
+                    Some(::h2s::schema::StructSchema {
+                        root: #root_schema,
+                        fields: vec![#(#field_schemas,)*],
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Adds bounds required to parse the type parameters used in the fields,
+/// `Parseable` for the ones in a field parsed by default and `FromHtml` for the ones in a flattened field.
+/// A type parameter only used by `parse_with` fields, or by no field, is left as declared
+fn build_generics(generics: &syn::Generics, fields: &[&H2sFieldReceiver]) -> syn::Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        let ident = param.ident.clone();
+        let used_by = |field: &&H2sFieldReceiver| mentions(field.ty.to_token_stream(), &ident);
+        if fields.iter().filter(|f| f.flatten).any(used_by) {
+            param.bounds.push(parse_quote!(::h2s::FromHtml));
+        }
+        if fields
+            .iter()
+            .filter(|f| !f.flatten && f.parse_with.is_none())
+            .any(used_by)
+        {
+            param.bounds.push(parse_quote!(::h2s::parseable::Parseable));
+        }
+    }
+    generics
+}
+
+/// Whether the tokens contain the identifier, e.g. `T` in `Vec<Option<T>>`
+fn mentions(tokens: proc_macro2::TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(i) => &i == ident,
+        proc_macro2::TokenTree::Group(g) => mentions(g.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Builds an expression to construct a struct (or an enum variant) from the fields
+/// The error of each field is wrapped into the variant of `error_ident` if specified, or `FieldError` otherwise
+fn build_struct_value(
+    path: proc_macro2::TokenStream,
+    fields: Vec<&H2sFieldReceiver>,
+    error_ident: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    let field_and_values = fields
+        .into_iter()
+        .enumerate()
+        .map(|(i, r)| r.build_field_and_value(i, error_ident));
+    quote!(#path { #(#field_and_values),* })
+}
+
+/// Builds an expression to construct a struct from the fields, which processes every field
+/// even if some of them fail, and collects all the errors
+fn build_collecting_struct_value(
+    fields: Vec<&H2sFieldReceiver>,
+    error_ident: &syn::Ident,
+    ty_generics: &syn::TypeGenerics,
+) -> proc_macro2::TokenStream {
+    if fields.is_empty() {
+        return quote!(Ok(Self {}));
+    }
+    let members = fields.iter().enumerate().map(|(i, f)| match &f.ident {
+        Some(id) => quote!(#id),
+        None => {
+            let i = syn::Index::from(i);
+            quote!(#i)
+        }
+    });
+    let bindings = (0..fields.len())
+        .map(|i| format_ident!("field{}", i))
+        .collect::<Vec<_>>();
+    let results = fields.iter().enumerate().map(|(i, f)| {
+        let ty = &f.ty;
+        let value = f.build_value(f.build_map_error(i, Some(error_ident)), true);
+        quote!(let result: Result<#ty, Vec<#error_ident #ty_generics>> = (|| Ok(#value))(); result)
+    });
+    quote! {
+        #(let #bindings = { #results };)*
+        match (#(#bindings,)*) {
+            (#(Ok(#bindings),)*) => Ok(Self { #(#members: #bindings),* }),
+            (#(#bindings,)*) => Err(::h2s::CollectedErrors {
+                errors: [#(#bindings.err()),*].into_iter().flatten().flatten().collect(),
+            }),
+        }
+    }
+}
+
+/// Builds a function that returns the default value of the field
+fn build_default(default: &Override<syn::Path>) -> proc_macro2::TokenStream {
+    match default {
+        Override::Inherit => quote!(::std::default::Default::default),
+        Override::Explicit(path) => quote!(#path),
+    }
+}
+
+/// Builds a statement that moves the input element to the descendant selected by the struct-level `root` selector
+fn build_select_root(
+    selector: &str,
+    span: proc_macro2::Span,
+    error_ident: &syn::Ident,
+    collect_errors: bool,
+) -> proc_macro2::TokenStream {
+    if let Err(e) = validate_selector(selector, span) {
+        return e.to_compile_error();
+    }
+    let select = build_cached_select(selector);
+    let map_error = if collect_errors {
+        quote!(|error| ::h2s::CollectedErrors {
+            errors: vec![#error_ident::Root(error)],
+        })
+    } else {
+        quote!(#error_ident::Root)
+    };
+    quote! {
+        let input = ::h2s::macro_utils::select_root(&input, #select).map_err(#map_error)?;
+    }
+}
+
+/// Builds an expression of `Select` whose selector is parsed only once by a static cache
+fn build_cached_select(selector: &str) -> proc_macro2::TokenStream {
+    quote!({
+        static SELECTOR: ::h2s::element_selector::SelectorCache =
+            ::h2s::element_selector::SelectorCache::new(#selector);
+        ::h2s::element_selector::Select::cached(&SELECTOR)
+    })
+}
+
+/// The type parsed by each element of the field value, e.g. `T` for `Vec<T>`, `Option<T>` and `[T; N]`
+fn inner_type(ty: &syn::Type) -> &syn::Type {
+    match ty {
+        syn::Type::Array(array) => &array.elem,
+        syn::Type::Path(p) => {
+            let inner = p.path.segments.last().and_then(|s| match &s.arguments {
+                syn::PathArguments::AngleBracketed(args)
+                    if s.ident == "Vec" || s.ident == "Option" =>
+                {
+                    match args.args.first() {
+                        Some(syn::GenericArgument::Type(inner)) => Some(inner),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            });
+            inner.unwrap_or(ty)
+        }
+        _ => ty,
+    }
+}
+
+/// Returns a compile error if the given css selector is syntactically invalid
+fn validate_selector(selector: &str, span: proc_macro2::Span) -> Result<(), syn::Error> {
+    h2s_core::selector_syntax::validate(selector)
+        .map_err(|e| syn::Error::new(span, format!("invalid css selector `{selector}`: {e}")))
+}
+
+impl H2sVariantReceiver {
+    /// Builds an expression that tries to parse the source element as this variant,
+    /// or evaluates `otherwise` if the condition of this variant doesn't match.
+    /// It fails without trying `otherwise` if the backend can't parse the condition
+    fn build_variant(&self, otherwise: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let variant_name = ident.to_string();
+        let value = build_struct_value(quote!(Self::#ident), self.fields.iter().collect(), None);
+        let parse_variant = quote! {
+            let result: Result<Self, ::h2s::FieldError> = (|| Ok(#value))();
+            // the first variant whose condition matches is the only candidate
+            result.map_err(|e| {
+                rejected.push(::h2s::RejectedVariant {
+                    variant_name: #variant_name.to_string(),
+                    reason: ::h2s::RejectionReason::FieldError(e),
+                });
+                ::h2s::VariantError { rejected }
+            })
+        };
+        match &self.when {
+            Some(selector) => {
+                if let Err(e) = validate_selector(selector, selector.span()) {
+                    return e.to_compile_error();
+                }
+                let selector = selector.as_str();
+                quote! {
+                    static CONDITION: ::h2s::element_selector::SelectorCache =
+                        ::h2s::element_selector::SelectorCache::new(#selector);
+                    match ::h2s::macro_utils::matches_condition(&input, &CONDITION) {
+                        Ok(true) => { #parse_variant }
+                        Ok(false) => {
+                            rejected.push(::h2s::RejectedVariant {
+                                variant_name: #variant_name.to_string(),
+                                reason: ::h2s::RejectionReason::ConditionNotMatched {
+                                    selector: #selector.to_string(),
+                                },
+                            });
+                            #otherwise
+                        }
+                        // it can't be told whether the following variants should be tried
+                        Err(e) => {
+                            rejected.push(::h2s::RejectedVariant {
+                                variant_name: #variant_name.to_string(),
+                                reason: ::h2s::RejectionReason::InvalidSelector(e),
+                            });
+                            Err(::h2s::VariantError { rejected })
+                        }
+                    }
+                }
+            }
+            // A variant without condition always matches
+            None => quote!({ #parse_variant }),
+        }
+    }
+
+    /// Builds an expression that tries to parse the source element as this variant,
+    /// or evaluates `otherwise` if any field of this variant fails
+    fn build_untagged_variant(
+        &self,
+        otherwise: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let variant_name = ident.to_string();
+        let value = build_struct_value(quote!(Self::#ident), self.fields.iter().collect(), None);
+        quote! {
+            let result: Result<Self, ::h2s::FieldError> = (|| Ok(#value))();
+            match result {
+                Ok(v) => Ok(v),
+                Err(e) => {
+                    rejected.push(::h2s::RejectedVariant {
+                        variant_name: #variant_name.to_string(),
+                        reason: ::h2s::RejectionReason::FieldError(e),
+                    });
+                    #otherwise
+                }
+            }
+        }
+    }
+}
+
 impl H2sFieldReceiver {
-    fn build_field_and_value(&self, index: usize) -> proc_macro2::TokenStream {
-        let (ident, field_name_str) = match &self.ident {
-            Some(id) => (quote!(#id), id.to_string()),
+    /// The extraction method specified by a shorthand attribute,
+    /// which is a unit struct so that the path is both of its type and value
+    fn shorthand_extraction_method(&self) -> Option<proc_macro2::TokenStream> {
+        if self.own_text {
+            Some(quote!(::h2s::extraction_method::ExtractOwnText))
+        } else if self.inner_html {
+            Some(quote!(::h2s::extraction_method::ExtractInnerHtml))
+        } else if self.outer_html {
+            Some(quote!(::h2s::extraction_method::ExtractOuterHtml))
+        } else {
+            None
+        }
+    }
+
+    /// Whether the type of the extraction method can't be named in the error type, which is either
+    /// the type of the `extractor` expression or the one inferred from the function of `parse_with`
+    fn erases_extraction_method(&self) -> bool {
+        self.extractor.is_some()
+            || (self.parse_with.is_some()
+                && self.attr.is_none()
+                && self.shorthand_extraction_method().is_none())
+    }
+
+    /// Builds an expression of `FieldSchema`
+    fn build_schema(&self, index: usize) -> proc_macro2::TokenStream {
+        let name = self.field_name(index);
+        let selector = match &self.select {
+            Some(selector) if !self.flatten => {
+                let selector = selector.as_str();
+                quote!(Some(#selector))
+            }
+            _ => quote!(None),
+        };
+        let is_vec = matches!(
+            &self.ty,
+            syn::Type::Path(p) if p.path.segments.last().map_or(false, |s| s.ident == "Vec")
+        );
+        let item_type = inner_type(&self.ty);
+        quote!(::h2s::schema::FieldSchema {
+            name: #name,
+            selector: #selector,
+            is_vec: #is_vec,
+            item_type: ::std::any::type_name::<#item_type>(),
+        })
+    }
+
+    /// The field name shown in error messages. The index is used for tuple struct
+    fn field_name(&self, index: usize) -> String {
+        match &self.ident {
+            Some(id) => id.unraw().to_string(),
+            None => index.to_string(),
+        }
+    }
+
+    /// The variant of the generated error type corresponding to this field,
+    /// e.g. `BlogTitle` for `blog_title` and `Field0` for the first field of tuple struct
+    fn error_variant_ident(&self, index: usize) -> syn::Ident {
+        if let Some(error) = &self.error {
+            return error.clone();
+        }
+        let name = match &self.ident {
+            Some(id) => id
+                .unraw()
+                .to_string()
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                        .unwrap_or_default()
+                })
+                .collect(),
+            None => format!("Field{index}"),
+        };
+        let span = self
+            .ident
+            .as_ref()
+            .map_or_else(|| self.ty.span(), |id| id.span());
+        syn::Ident::new(&name, span)
+    }
+
+    /// Builds the type of the error that can occur on processing this field.
+    /// It shouldn't depend on the type of HTML element since it's a part of the generated error type
+    fn build_error_type(&self) -> proc_macro2::TokenStream {
+        let ty = &self.ty;
+        if self.flatten {
+            return quote!(<#ty as ::h2s::FromHtml>::Error);
+        }
+        let selector = if self.select.is_some() {
+            quote!(::h2s::element_selector::Select)
+        } else {
+            quote!(::h2s::element_selector::Root)
+        };
+        let extraction_method = if self.attr.is_some() {
+            quote!(::h2s::extraction_method::ExtractAttribute)
+        } else if let Some(path) = self.shorthand_extraction_method() {
+            path
+        } else if self.erases_extraction_method() {
+            quote!(::h2s::macro_utils::ErasedExtractionMethod)
+        } else {
+            quote!(::h2s::extraction_method::ExtractDefault<<#ty as ::h2s::field_value::FieldValue>::Inner>)
+        };
+        if self.parse_with.is_some() {
+            let inner = inner_type(ty);
+            quote!(::h2s::macro_utils::FieldProcessWithError<#ty, #inner, #selector, #extraction_method>)
+        } else {
+            quote!(::h2s::macro_utils::FieldProcessError<#ty, #selector, #extraction_method>)
+        }
+    }
+
+    fn build_field_and_value(
+        &self,
+        index: usize,
+        error_ident: Option<&syn::Ident>,
+    ) -> proc_macro2::TokenStream {
+        let ident = match &self.ident {
+            Some(id) => quote!(#id),
             None => {
                 let i = syn::Index::from(index);
-                (quote!(#i), index.to_string())
+                quote!(#i)
             }
         };
-        let value = self.build_value(&field_name_str);
+        if error_ident.is_none() && self.error.is_some() {
+            // the fields of enum variants have no error type of their own
+            let error = syn::Error::new(self.ty.span(), "`error` is only available for struct")
+                .to_compile_error();
+            return quote!(#ident: #error);
+        }
+        let value = self.build_value(self.build_map_error(index, error_ident), false);
         quote!(#ident: #value)
     }
 
-    fn build_value(&self, field_name: &String) -> proc_macro2::TokenStream {
+    /// Builds a function to convert the error of this field into the variant of `error_ident`
+    /// if specified, or `FieldError` otherwise
+    fn build_map_error(
+        &self,
+        index: usize,
+        error_ident: Option<&syn::Ident>,
+    ) -> proc_macro2::TokenStream {
+        match error_ident {
+            Some(error_ident) => {
+                let variant = self.error_variant_ident(index);
+                quote!(#error_ident::#variant)
+            }
+            None => {
+                // The error of the flattened struct is shown as if it's the error of the parent
+                let field_name = if self.flatten {
+                    String::new()
+                } else {
+                    self.field_name(index)
+                };
+                let paths = self.build_error_paths(index, quote!(&error));
+                quote!(|error| ::h2s::FieldError {
+                    field_name: #field_name.to_string(),
+                    paths: #paths,
+                    error: Box::new(error),
+                })
+            }
+        }
+    }
+
+    /// Builds an expression that returns the paths of the given error of this field
+    fn build_error_paths(
+        &self,
+        index: usize,
+        error: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let ty = &self.ty;
+        if self.flatten {
+            // the fields of the flattened struct are shown as if they're the fields of the parent
+            return quote!(<#ty as ::h2s::FromHtml>::error_paths(#error));
+        }
+        let field_name = self.field_name(index);
+        let selector = match &self.select {
+            Some(selector) => {
+                let selector = selector.as_str();
+                quote!(Some(#selector))
+            }
+            None => quote!(None),
+        };
+        let inner_paths = if self.parse_with.is_some() {
+            quote!(|_| vec![vec![]])
+        } else {
+            quote!(<<#ty as ::h2s::field_value::FieldValue>::Inner as ::h2s::parseable::Parseable>::error_paths)
+        };
+        quote!(::h2s::macro_utils::field_error_paths(#field_name, #selector, #error, #inner_paths))
+    }
+
+    /// Builds an expression to process the field value,
+    /// which converts its error by `map_error` and returns it early.
+    /// If `collect` is true, it processes every item of the field and returns all the errors as `Vec`
+    fn build_value(
+        &self,
+        map_error: proc_macro2::TokenStream,
+        collect: bool,
+    ) -> proc_macro2::TokenStream {
+        if self.flatten {
+            return self.build_flatten_value(map_error, collect);
+        }
+
         let selector = match &self.select {
             Some(selector) => {
                 // check selector validity at compile time
-                if Selector::parse(selector).is_err() {
-                    return syn::Error::new(
-                        // TODO highlight the span of macro attribute, not field ident and type
-                        self.ident
-                            .as_ref()
-                            .and_then(|id| id.span().join(self.ty.span()))
-                            .unwrap_or_else(|| self.ty.span()),
-                        format!("invalid css selector: `{selector}`"),
-                    )
-                    .to_compile_error();
+                if let Err(e) = validate_selector(selector, selector.span()) {
+                    return e.to_compile_error();
                 }
-                quote!(::h2s::element_selector::Select{ selector: #selector.to_string() })
+                build_cached_select(selector)
             }
             None => quote!(::h2s::element_selector::Root),
         };
 
+        let extraction_methods = [
+            self.attr.is_some(),
+            self.extractor.is_some(),
+            self.own_text,
+            self.inner_html,
+            self.outer_html,
+        ];
+        if extraction_methods.iter().filter(|&&b| b).count() > 1 {
+            return syn::Error::new(
+                self.ty.span(),
+                "only one of `attr`, `extractor`, `own_text`, `inner_html` and `outer_html` can be specified",
+            )
+            .to_compile_error();
+        }
+
         // TODO user‐unfriendly error message is shown when argument is mismatched
         let extraction_method = if let Some(attr) = self.attr.as_ref() {
             quote!(::h2s::macro_utils::extraction_method(::h2s::extraction_method::ExtractAttribute{ name: #attr .to_string() }))
+        } else if let Some(path) = self.shorthand_extraction_method() {
+            quote!(::h2s::macro_utils::extraction_method(#path))
         } else if let Some(a) = self.extractor.as_ref() {
             quote!(::h2s::macro_utils::extraction_method(#a))
+        } else if self.parse_with.is_some() {
+            // inferred by the argument type of the function specified by `parse_with`
+            quote!(::h2s::macro_utils::parse_with_extraction_method(
+                &input, &parse
+            ))
+        } else {
+            quote!(::h2s::macro_utils::default_extraction_method())
+        };
+
+        let error_mode = if collect {
+            quote!(::h2s::macro_utils::AllErrors)
         } else {
-            quote!(::h2s::macro_utils::default_extraction_method::<E, _>())
+            quote!(::h2s::macro_utils::FirstError)
+        };
+        let process_field = match (&self.parse_with, &self.default) {
+            (None, None) => {
+                quote!(::h2s::macro_utils::process_field(
+                    &input,
+                    selector,
+                    extraction_method,
+                    #error_mode,
+                ))
+            }
+            (None, Some(default)) => {
+                let default = build_default(default);
+                quote!(::h2s::macro_utils::process_field_or_else(&input, selector, extraction_method, #error_mode, #default))
+            }
+            (Some(_), None) => {
+                quote!(::h2s::macro_utils::process_field_with(
+                    &input,
+                    selector,
+                    extraction_method,
+                    |v| parse(v).map_err(::h2s::macro_utils::ParseWithError::new),
+                    #error_mode,
+                ))
+            }
+            (Some(_), Some(default)) => {
+                let default = build_default(default);
+                quote!(::h2s::macro_utils::process_field_with_or_else(
+                    &input,
+                    selector,
+                    extraction_method,
+                    |v| parse(v).map_err(::h2s::macro_utils::ParseWithError::new),
+                    #error_mode,
+                    #default,
+                ))
+            }
+        };
+
+        let parse = self.parse_with.as_ref().map(|p| quote!(let parse = #p;));
+        let erase = self
+            .erases_extraction_method()
+            .then(|| quote!(::h2s::macro_utils::erase_extraction_method));
+        let map_error = match (collect, erase) {
+            (true, erase) => {
+                let erase = erase.map(|erase| quote!(.map(#erase)));
+                quote!(|errors| errors.into_iter() #erase .map(#map_error).collect::<Vec<_>>())
+            }
+            (false, Some(erase)) => quote!(|error| (#map_error)(#erase(error))),
+            (false, None) => map_error,
         };
 
         quote!({
-            let field_name = #field_name.to_string();
             let selector = #selector;
+            #parse
             let extraction_method = #extraction_method;
-            ::h2s::macro_utils::process_field(&input, selector, extraction_method)
-                .map_err(|error| ::h2s::FieldError {
-                    field_name,
-                    error: Box::new(error),
-                })?
+            #process_field.map_err(#map_error)?
+        })
+    }
+
+    fn build_flatten_value(
+        &self,
+        map_error: proc_macro2::TokenStream,
+        collect: bool,
+    ) -> proc_macro2::TokenStream {
+        if self.select.is_some()
+            || self.attr.is_some()
+            || self.shorthand_extraction_method().is_some()
+            || self.extractor.is_some()
+            || self.default.is_some()
+            || self.parse_with.is_some()
+        {
+            return syn::Error::new(
+                self.ty.span(),
+                "`flatten` can't be used with other attributes",
+            )
+            .to_compile_error();
+        }
+        let ty = &self.ty;
+        let map_error = if collect {
+            quote!(|error| vec![(#map_error)(error)])
+        } else {
+            map_error
+        };
+        quote!(<#ty as ::h2s::FromHtml>::from_html(input.clone()).map_err(#map_error)?)
+    }
+}
+
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(h2s), supports(enum_unit))]
+struct ParseableEnumReceiver {
+    ident: syn::Ident,
+    data: Data<ParseableVariantReceiver, ()>,
+
+    /// Compares the input with variant values case-insensitively
+    #[darling(default)]
+    case_insensitive: bool,
+    /// Ignores leading and trailing whitespaces of the input
+    #[darling(default)]
+    trim: bool,
+}
+
+#[derive(Debug, FromVariant)]
+#[darling(attributes(h2s))]
+struct ParseableVariantReceiver {
+    ident: syn::Ident,
+
+    /// A value corresponding to the variant. The variant name is used if not specified
+    rename: Option<String>,
+    /// Additional values accepted as the variant
+    #[darling(multiple)]
+    alias: Vec<String>,
+}
+
+impl ToTokens for ParseableEnumReceiver {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend(self.build().unwrap_or_else(|e| e.to_compile_error()));
+    }
+}
+
+impl ParseableEnumReceiver {
+    fn build(&self) -> Result<proc_macro2::TokenStream, syn::Error> {
+        let Self {
+            ref ident,
+            ref data,
+            case_insensitive,
+            trim,
+        } = *self;
+
+        let variants = match data.as_ref() {
+            Data::Enum(variants) => variants,
+            // `supports(enum_unit)` rejects structs
+            Data::Struct(_) => unreachable!(),
+        };
+
+        let normalize = |value: &str| {
+            if case_insensitive {
+                value.to_lowercase()
+            } else {
+                value.to_string()
+            }
+        };
+
+        let mut accepted: Vec<String> = vec![];
+        let mut arms = vec![];
+        for variant in variants {
+            let variant_ident = &variant.ident;
+            let values = variant
+                .rename
+                .clone()
+                .unwrap_or_else(|| variant_ident.to_string());
+            let values = std::iter::once(values)
+                .chain(variant.alias.iter().cloned())
+                .collect::<Vec<_>>();
+            for value in &values {
+                if accepted.iter().any(|a| normalize(a) == normalize(value)) {
+                    return Err(syn::Error::new(
+                        variant_ident.span(),
+                        format!("the value `{value}` is duplicated"),
+                    ));
+                }
+                accepted.push(value.clone());
+            }
+            let patterns = values.iter().map(|v| normalize(v));
+            arms.push(quote!(#(#patterns)|* => Ok(Self::#variant_ident)));
+        }
+
+        let trimmed = if trim {
+            quote!(input.trim())
+        } else {
+            quote!(input.as_str())
+        };
+        let normalized = if case_insensitive {
+            quote!(#trimmed.to_lowercase().as_str())
+        } else {
+            trimmed
+        };
+
+        Ok(quote! {
+            impl ::h2s::parseable::Parseable for #ident {
+                type Input<N: ::h2s::html::HtmlElement> = String;
+                type Error = ::h2s::parseable::UnknownEnumValue;
+
+                fn parse<N: ::h2s::html::HtmlElement>(input: Self::Input<N>) -> Result<Self, Self::Error> {
+                    match #normalized {
+                        #(#arms,)*
+                        _ => Err(::h2s::parseable::UnknownEnumValue {
+                            value: input,
+                            accepted: vec![#(#accepted.to_string()),*],
+                        }),
+                    }
+                }
+            }
         })
     }
 }
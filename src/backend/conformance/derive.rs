@@ -0,0 +1,1484 @@
+//! The test cases of the derive macro, which are applicable to any backend
+
+use h2s_core::html::Backend;
+
+use crate::{parse_with_backend, FromHtml, ParseableEnum};
+
+/// Compares error messages ignoring the source locations, which are backend-specific
+macro_rules! assert_message {
+    ($actual:expr, $expected:expr $(, $($arg:tt)+)?) => {
+        assert_eq!(
+            without_locations(&$actual.to_string()),
+            without_locations($expected)
+            $(, $($arg)+)?
+        )
+    };
+}
+
+/// Passes the test cases to the macro
+macro_rules! derive_cases {
+    ($m:ident) => {
+        $m! {
+            string_values,
+            own_text,
+            html_values,
+            nested_struct,
+            struct_unnamed,
+            tagged_enum,
+            tagged_enum_fallback,
+            untagged_enum,
+            parseable_enum,
+            default_value,
+            parse_with,
+            flatten,
+            struct_root,
+            generic_struct,
+            typed_error,
+            extractor_expression,
+            renamed_error,
+            collect_errors,
+            error_paths,
+            fragment,
+            document,
+            parse_all,
+        }
+    };
+}
+
+pub(super) fn run<B>()
+where
+    B: Backend,
+{
+    derive_cases!(run_cases);
+}
+
+fn string_values<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Struct1 {
+        // Single String, attr
+        #[h2s(attr = "lang")]
+        a: String,
+        // Single String, select
+        #[h2s(select = ".b")]
+        b: String,
+        // Single String, attr & select
+        #[h2s(select = ".c", attr = "id")]
+        c: String,
+        // Vec
+        #[h2s(select = ".d")]
+        d: Vec<String>,
+        // Option - Some
+        #[h2s(select = ".e")]
+        e: Option<String>,
+        // Option - None
+        #[h2s(select = ".class-that-does-not-exist")]
+        e_none: Option<String>,
+        // Array
+        #[h2s(select = ".f")]
+        f: [String; 3],
+        #[h2s(select = ".g")]
+        g: Struct2,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Struct2 {
+        // An inner text of struct root element
+        #[h2s()]
+        h: String,
+    }
+
+    let html = r#"
+<!DOCTYPE html>
+<html lang="avalue">
+<body>
+
+<div class="b">bvalue</div>
+
+<div class="c" id="cvalue" />
+
+<div class="d">dvalue1</div>
+<div class="d">dvalue2</div>
+<div class="d">dvalue3</div>
+
+<div class="e">evalue</div>
+
+<div class="f">fvalue1</div>
+<div class="f">fvalue2</div>
+<div class="f">fvalue3</div>
+    
+<div class="g">hvalue</div>
+
+</body>
+</html>
+    "#;
+
+    let res = parse_with_backend::<Struct1, B>(html);
+    assert_eq!(
+        res.unwrap(),
+        Struct1 {
+            a: s("avalue"),
+            b: s("bvalue"),
+            c: s("cvalue"),
+            d: vec![s("dvalue1"), s("dvalue2"), s("dvalue3")],
+            e: Some(s("evalue")),
+            e_none: None,
+            f: [s("fvalue1"), s("fvalue2"), s("fvalue3")],
+            g: Struct2 { h: s("hvalue") }
+        }
+    )
+}
+
+fn own_text<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Article {
+        #[h2s(select = ".views", own_text)]
+        views_label: String,
+        #[h2s(select = ".views > span")]
+        views: usize,
+        #[h2s(select = "li", own_text)]
+        tags: Vec<String>,
+        #[h2s(select = ".count", own_text, parse_with = parse_count)]
+        count: usize,
+        #[h2s(select = ".empty", own_text)]
+        empty: String,
+    }
+
+    fn parse_count(s: String) -> Result<usize, std::num::ParseIntError> {
+        s.trim_end_matches(" items").parse()
+    }
+
+    let html = r#"
+<div class="views"><span>901</span> Views</div>
+<ul><li>a <b>(new)</b></li><li><b>(old)</b> b</li></ul>
+<div class="count">3 items<!-- comment --></div>
+<div class="empty"><span>x</span></div>
+"#;
+    assert_eq!(
+        parse_with_backend::<Article, B>(html).unwrap(),
+        Article {
+            views_label: s("Views"),
+            views: 901,
+            tags: vec![s("a"), s("b")],
+            count: 3,
+            empty: s(""),
+        },
+        "the texts of the descendant elements are excluded"
+    );
+}
+
+fn html_values<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Article {
+        #[h2s(select = "h2", outer_html)]
+        title: String,
+        #[h2s(select = ".body", inner_html)]
+        body: String,
+        #[h2s(select = ".body > p", inner_html)]
+        paragraphs: Vec<String>,
+        #[h2s(select = ".note", inner_html)]
+        note: Option<String>,
+    }
+
+    let html = r#"
+<h2 class="title">a</h2>
+<div class="body"><p>1<em>2</em></p><p>3</p></div>
+"#;
+    assert_eq!(
+        parse_with_backend::<Article, B>(html).unwrap(),
+        Article {
+            title: s(r#"<h2 class="title">a</h2>"#),
+            body: s("<p>1<em>2</em></p><p>3</p>"),
+            paragraphs: vec![s("1<em>2</em>"), s("3")],
+            note: None,
+        }
+    );
+}
+
+fn nested_struct<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Struct {
+        // Single Struct, parse root element itself
+        #[h2s]
+        a: StructInner1,
+        // Single Struct, select
+        #[h2s(select = ".b")]
+        b: StructInner2,
+        // Vec
+        #[h2s(select = ".c")]
+        c: Vec<StructInner2>,
+        // Option - Some
+        #[h2s(select = ".d")]
+        d: Option<StructInner2>,
+        // Option - None
+        #[h2s(select = ".class-that-does-not-exist")]
+        d_none: Option<StructInner2>,
+        // Array
+        #[h2s(select = ".e")]
+        e: [StructInner2; 3],
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct StructInner1 {
+        #[h2s(attr = "lang")]
+        a: String,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct StructInner2 {
+        #[h2s(select = "span")]
+        v: String,
+    }
+
+    let html = r#"
+<!DOCTYPE html>
+<html lang="a">
+<body>
+<div class="b"><span>bvalue</span></div>
+
+<div class="c"><span>cvalue1</span></div>
+<div class="c"><span>cvalue2</span></div>
+<div class="c"><span>cvalue3</span></div>
+
+<div class="d"><span>dvalue</span></div>
+
+<div class="e"><span>evalue1</span></div>
+<div class="e"><span>evalue2</span></div>
+<div class="e"><span>evalue3</span></div>
+</body>
+</html>
+    "#;
+
+    let res = parse_with_backend::<Struct, B>(html);
+
+    let si2 = |s: &str| StructInner2 { v: s.to_string() };
+
+    assert_eq!(
+        res.unwrap(),
+        Struct {
+            a: StructInner1 { a: s("a") },
+            b: si2("bvalue"),
+            c: vec![si2("cvalue1"), si2("cvalue2"), si2("cvalue3")],
+            d: Some(si2("dvalue")),
+            d_none: None,
+            e: [si2("evalue1"), si2("evalue2"), si2("evalue3")],
+        }
+    )
+}
+
+fn struct_unnamed<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Struct(
+        // Single Struct, parse root element itself
+        #[h2s] StructInner1,
+        // Single Struct, select
+        #[h2s(select = ".b")] StructInner2,
+        // Vec
+        #[h2s(select = ".c")] Vec<StructInner2>,
+        // Option - Some
+        #[h2s(select = ".d")] Option<StructInner2>,
+        // Option - None
+        #[h2s(select = ".class-that-does-not-exist")] Option<StructInner2>,
+        // Array
+        #[h2s(select = ".e")] [StructInner2; 3],
+    );
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct StructInner1(#[h2s(attr = "lang")] String);
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct StructInner2(#[h2s(select = "span")] String);
+
+    let html = r#"
+<!DOCTYPE html>
+<html lang="a">
+<body>
+<div class="b"><span>bvalue</span></div>
+
+<div class="c"><span>cvalue1</span></div>
+<div class="c"><span>cvalue2</span></div>
+<div class="c"><span>cvalue3</span></div>
+
+<div class="d"><span>dvalue</span></div>
+
+<div class="e"><span>evalue1</span></div>
+<div class="e"><span>evalue2</span></div>
+<div class="e"><span>evalue3</span></div>
+</body>
+</html>
+    "#;
+
+    let res = parse_with_backend::<Struct, B>(html);
+
+    let si2 = |s: &str| StructInner2(s.to_string());
+
+    assert_eq!(
+        res.unwrap(),
+        Struct(
+            StructInner1(s("a")),
+            si2("bvalue"),
+            vec![si2("cvalue1"), si2("cvalue2"), si2("cvalue3")],
+            Some(si2("dvalue")),
+            None,
+            [si2("evalue1"), si2("evalue2"), si2("evalue3")],
+        )
+    )
+}
+
+fn tagged_enum<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Page {
+        #[h2s(select = ".card")]
+        cards: Vec<Card>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub enum Card {
+        #[h2s(when = ".ad")]
+        Ad,
+        #[h2s(when = ".article")]
+        Article {
+            #[h2s(select = "h2")]
+            title: String,
+        },
+        #[h2s(when = ".video")]
+        Video(#[h2s(select = "a", attr = "href")] String),
+    }
+
+    let html = r#"
+<html>
+<body>
+<div class="card ad"><h2>buy now</h2></div>
+<div class="card article"><h2>article1</h2></div>
+<div class="card video"><a href="https://example.com/v">video</a></div>
+</body>
+</html>
+    "#;
+
+    assert_eq!(
+        parse_with_backend::<Page, B>(html).unwrap(),
+        Page {
+            cards: vec![
+                Card::Ad,
+                Card::Article {
+                    title: s("article1")
+                },
+                Card::Video(s("https://example.com/v")),
+            ]
+        }
+    );
+
+    assert_message!(
+        parse_with_backend::<Page, B>(r#"<div class="card image"></div>"#)
+            .unwrap_err()
+            .to_string(),
+        "cards: [0] at line 1: no variant matched: \
+         [Ad: the element doesn't match \".ad\"], \
+         [Article: the element doesn't match \".article\"], \
+         [Video: the element doesn't match \".video\"]"
+    );
+
+    assert_message!(
+        parse_with_backend::<Page, B>(r#"<div class="card article video"></div>"#)
+            .unwrap_err()
+            .to_string(),
+        "cards: [0] at line 1: no variant matched: \
+         [Ad: the element doesn't match \".ad\"], \
+         [Article: title: mismatched number of selected elements by \"h2\": expected exactly one element, but no elements found]",
+        "the later variants are not tried once a condition matches"
+    );
+}
+
+fn tagged_enum_fallback<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub enum Price {
+        #[h2s(when = ".free")]
+        Free,
+        Paid(#[h2s(select = ".amount")] u32),
+    }
+
+    assert_eq!(
+        parse_with_backend::<Price, B>(r#"<html class="free"></html>"#).unwrap(),
+        Price::Free
+    );
+    assert_eq!(
+        parse_with_backend::<Price, B>(r#"<html><span class="amount">100</span></html>"#).unwrap(),
+        Price::Paid(100)
+    );
+}
+
+fn untagged_enum<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(untagged)]
+    pub enum Layout {
+        // A layout of the B variant of A/B testing
+        New {
+            #[h2s(select = ".product .name")]
+            name: String,
+            #[h2s(select = ".product .price")]
+            price: u32,
+        },
+        Old {
+            #[h2s(select = "h1")]
+            name: String,
+            #[h2s(select = "#price")]
+            price: u32,
+        },
+    }
+
+    assert_eq!(
+        parse_with_backend::<Layout, B>(
+            r#"<div class="product"><div class="name">foo</div><div class="price">100</div></div>"#
+        )
+        .unwrap(),
+        Layout::New {
+            name: s("foo"),
+            price: 100
+        }
+    );
+    assert_eq!(
+        parse_with_backend::<Layout, B>(r#"<h1>foo</h1><span id="price">100</span>"#).unwrap(),
+        Layout::Old {
+            name: s("foo"),
+            price: 100
+        }
+    );
+    assert_message!(
+        parse_with_backend::<Layout, B>(r#"<h1>foo</h1><span id="price">free</span>"#)
+            .unwrap_err()
+            .to_string(),
+        "no variant matched: \
+         [New: name: mismatched number of selected elements by \".product .name\": expected exactly one element, but no elements found], \
+         [Old: price: at line 1: invalid digit found in string]",
+        "errors of all variants are collected"
+    );
+}
+
+fn parseable_enum<B>()
+where
+    B: Backend,
+{
+    #[derive(ParseableEnum, Debug, Eq, PartialEq)]
+    pub enum Status {
+        #[h2s(rename = "In stock", alias = "Available")]
+        InStock,
+        #[h2s(rename = "Sold out", alias = "Out of stock", alias = "N/A")]
+        SoldOut,
+        Discontinued,
+    }
+
+    #[derive(ParseableEnum, Debug, Eq, PartialEq)]
+    #[h2s(case_insensitive, trim)]
+    pub enum Currency {
+        #[h2s(rename = "usd")]
+        Usd,
+        #[h2s(rename = "jpy")]
+        Jpy,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Product {
+        #[h2s(select = ".status")]
+        statuses: Vec<Status>,
+        #[h2s(select = ".price", attr = "data-currency")]
+        currency: Currency,
+    }
+
+    let html = r#"
+<div class="status">In stock</div>
+<div class="status">Available</div>
+<div class="status">N/A</div>
+<div class="status">Discontinued</div>
+<div class="price" data-currency=" JPY ">100</div>
+    "#;
+    assert_eq!(
+        parse_with_backend::<Product, B>(html).unwrap(),
+        Product {
+            statuses: vec![
+                Status::InStock,
+                Status::InStock,
+                Status::SoldOut,
+                Status::Discontinued
+            ],
+            currency: Currency::Jpy,
+        }
+    );
+
+    assert_message!(
+        parse_with_backend::<Product, B>(html.replace("N/A", "n/a"))
+            .unwrap_err()
+            .to_string(),
+        "statuses: [2] at line 4: unknown value \"n/a\": expected one of \
+         \"In stock\", \"Available\", \"Sold out\", \"Out of stock\", \"N/A\", \"Discontinued\""
+    );
+}
+
+fn default_value<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Product {
+        #[h2s(select = ".price", default)]
+        price: u32,
+        #[h2s(select = ".name", default = "unknown_name")]
+        name: String,
+        #[h2s(select = ".image", attr = "src", default = "unknown_name")]
+        image: String,
+        #[h2s(select = ".tags", attr = "data-tags", default)]
+        tags: String,
+    }
+
+    fn unknown_name() -> String {
+        s("unknown")
+    }
+
+    assert_eq!(
+        parse_with_backend::<Product, B>(r#"<div class="image"></div>"#).unwrap(),
+        Product {
+            price: 0,
+            name: s("unknown"),
+            image: s("unknown"),
+            tags: s(""),
+        },
+        "falls back to the default value when the element or the attribute is missing"
+    );
+    assert_eq!(
+        parse_with_backend::<Product, B>(
+            r#"<div class="price">100</div><div class="name">foo</div><img class="image" src="foo.png"/><div class="tags" data-tags="a,b"></div>"#
+        )
+        .unwrap(),
+        Product {
+            price: 100,
+            name: s("foo"),
+            image: s("foo.png"),
+            tags: s("a,b"),
+        }
+    );
+    assert_message!(
+        parse_with_backend::<Product, B>(r#"<div class="price">free</div>"#)
+            .unwrap_err()
+            .to_string(),
+        "price: at line 1: invalid digit found in string",
+        "parse error doesn't fall back to the default value"
+    );
+    assert_eq!(
+        parse_with_backend::<Product, B>(r#"<div class="price">1</div><div class="price">2</div>"#)
+            .unwrap_err()
+            .to_string(),
+        "price: mismatched number of selected elements by \".price\": expected exactly one element, but 2 elements found",
+        "too many elements doesn't fall back to the default value"
+    );
+}
+
+fn parse_with<B>()
+where
+    B: Backend,
+{
+    use crate::extraction_method::NoOp;
+    use std::time::Duration;
+
+    mod my_mod {
+        use crate::extraction_method::ExtractionMethod;
+        use crate::html::HtmlElement;
+        use crate::parseable::ExtractedValue;
+        use crate::Never;
+        use std::fmt::{Display, Formatter};
+        use std::num::ParseIntError;
+        use std::time::Duration;
+
+        /// The input type of a function, whose default extraction method is used for the function
+        pub struct Classes(pub Vec<String>);
+
+        #[derive(Debug, Clone)]
+        pub struct ExtractClasses;
+
+        impl Display for ExtractClasses {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "classes")
+            }
+        }
+
+        impl ExtractionMethod for ExtractClasses {
+            type Error = Never;
+            type ExtractedValue<N: HtmlElement> = Classes;
+
+            fn extract<N>(&self, element: N) -> Result<Self::ExtractedValue<N>, Self::Error>
+            where
+                N: HtmlElement,
+            {
+                let class = element.attribute("class").unwrap_or_default();
+                Ok(Classes(
+                    class.split_whitespace().map(str::to_string).collect(),
+                ))
+            }
+        }
+
+        impl<N: HtmlElement> ExtractedValue<N> for Classes {
+            type Default = ExtractClasses;
+            fn default_method() -> Self::Default {
+                ExtractClasses
+            }
+        }
+
+        pub fn parse_class_count(classes: Classes) -> Result<usize, Never> {
+            Ok(classes.0.len())
+        }
+
+        pub fn parse_duration(s: String) -> Result<Duration, ParseIntError> {
+            Ok(Duration::from_secs(s.trim_end_matches('s').parse()?))
+        }
+
+        pub fn parse_child_count<N: HtmlElement>(element: N) -> Result<usize, crate::Never> {
+            Ok(element.child_nodes().len())
+        }
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Video {
+        #[h2s(select = "time", parse_with = "my_mod::parse_duration")]
+        length: Duration,
+        #[h2s(
+            select = "time",
+            attr = "data-sec",
+            parse_with = "my_mod::parse_duration"
+        )]
+        length2: Duration,
+        #[h2s(select = ".chapter", parse_with = "my_mod::parse_duration")]
+        chapters: Vec<Duration>,
+        #[h2s(select = ".preview", parse_with = "my_mod::parse_duration", default)]
+        preview: Option<Duration>,
+        #[h2s(select = "ul", extractor = NoOp, parse_with = "my_mod::parse_child_count")]
+        child_count: usize,
+        #[h2s(select = "ul", parse_with = "my_mod::parse_class_count")]
+        class_count: usize,
+    }
+
+    let html = r#"
+<time data-sec="90">90s</time>
+<div class="chapter">0s</div>
+<div class="chapter">30s</div>
+<ul class="list dense"><li>a</li><li>b</li></ul>
+    "#;
+    assert_eq!(
+        parse_with_backend::<Video, B>(html).unwrap(),
+        Video {
+            length: Duration::from_secs(90),
+            length2: Duration::from_secs(90),
+            chapters: vec![Duration::from_secs(0), Duration::from_secs(30)],
+            preview: None,
+            child_count: 2,
+            class_count: 2,
+        },
+        "the extraction method is inferred from the argument type of the function"
+    );
+    assert_message!(
+        parse_with_backend::<Video, B>(html.replace("30s", "3O"))
+            .unwrap_err()
+            .to_string(),
+        "chapters: [1] at line 4: invalid digit found in string"
+    );
+}
+
+fn flatten<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct SeoMeta {
+        #[h2s(select = r#"meta[property="og:title"]"#, attr = "content")]
+        og_title: String,
+        #[h2s(select = r#"meta[property="og:image"]"#, attr = "content")]
+        og_image: String,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct ArticlePage {
+        #[h2s(flatten)]
+        seo: SeoMeta,
+        #[h2s(select = "h1")]
+        title: String,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Page {
+        #[h2s(select = ".article")]
+        article: ArticlePage,
+    }
+
+    let html = r#"
+<html>
+<head>
+<meta property="og:title" content="Title" />
+<meta property="og:image" content="https://example.com/a.png" />
+</head>
+<body><div class="article"><h1>Title</h1></div></body>
+</html>
+    "#;
+    assert_eq!(
+        parse_with_backend::<ArticlePage, B>(html).unwrap(),
+        ArticlePage {
+            seo: SeoMeta {
+                og_title: s("Title"),
+                og_image: s("https://example.com/a.png"),
+            },
+            title: s("Title"),
+        }
+    );
+
+    let html = r#"
+<div class="article">
+<meta property="og:title" content="Title" />
+<h1>Title</h1>
+</div>
+    "#;
+    assert_message!(
+        parse_with_backend::<Page, B>(html).unwrap_err().to_string(),
+        "article: at line 2: og_image: mismatched number of selected elements by \"meta[property=\"og:image\"]\": expected exactly one element, but no elements found",
+        "no extra path segment for the flattened field"
+    );
+}
+
+fn struct_root<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(root = "article.main")]
+    pub struct Content {
+        #[h2s(select = "h1")]
+        title: String,
+        #[h2s(select = "p")]
+        paragraphs: Vec<String>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Page {
+        #[h2s(select = "body")]
+        content: Content,
+    }
+
+    let html = r#"
+<html>
+<body>
+<h1>Site</h1>
+<article class="main"><h1>Title</h1><p>a</p><p>b</p></article>
+<article class="sub"><p>c</p></article>
+</body>
+</html>
+    "#;
+    assert_eq!(
+        parse_with_backend::<Page, B>(html).unwrap(),
+        Page {
+            content: Content {
+                title: s("Title"),
+                paragraphs: vec![s("a"), s("b")],
+            }
+        }
+    );
+    assert_message!(
+        parse_with_backend::<Page, B>(html.replace("sub", "main"))
+            .unwrap_err()
+            .to_string(),
+        "content: at line 3: mismatched number of selected elements by \"article.main\": expected exactly one element, but 2 elements found"
+    );
+}
+
+fn generic_struct<B>()
+where
+    B: Backend,
+{
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Paginated<T> {
+        #[h2s(select = ".item")]
+        items: Vec<T>,
+        #[h2s(select = ".next", attr = "href")]
+        next: Option<String>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Single<T, U>
+    where
+        U: Clone,
+    {
+        #[h2s(select = ".item:nth-child(1)")]
+        first: T,
+        #[h2s(select = ".item:nth-child(2)")]
+        second: Option<U>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Top<T, const N: usize> {
+        #[h2s(select = ".item")]
+        items: [T; N],
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Borrowed<'a, T> {
+        #[h2s(select = ".item")]
+        items: Vec<T>,
+        #[h2s(select = ".next")]
+        label: Label<'a>,
+    }
+
+    // `U` isn't `Parseable`, which is fine since it's only parsed by `parse_with`
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Link<U>
+    where
+        U: From<String>,
+    {
+        #[h2s(select = ".next", attr = "href", parse_with = "parse_into")]
+        href: U,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Item {
+        #[h2s(select = "span")]
+        id: u32,
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    pub struct Label<'a>(std::borrow::Cow<'a, str>);
+
+    impl<'a> crate::parseable::Parseable for Label<'a> {
+        type Input<N: crate::html::HtmlElement> = String;
+        type Error = std::convert::Infallible;
+
+        fn parse<N: crate::html::HtmlElement>(input: String) -> Result<Self, Self::Error> {
+            Ok(Label(input.into()))
+        }
+    }
+
+    fn parse_into<U>(s: String) -> Result<U, std::convert::Infallible>
+    where
+        U: From<String>,
+    {
+        Ok(s.into())
+    }
+
+    let html = r#"
+<div>
+<div class="item"><span>1</span></div>
+<div class="item"><span>2</span></div>
+</div>
+<a class="next" href="/page/2">next</a>
+    "#;
+
+    assert_eq!(
+        parse_with_backend::<Paginated<Item>, B>(html).unwrap(),
+        Paginated {
+            items: vec![Item { id: 1 }, Item { id: 2 }],
+            next: Some(s("/page/2")),
+        }
+    );
+    assert_eq!(
+        parse_with_backend::<Paginated<u32>, B>(html).unwrap(),
+        Paginated {
+            items: vec![1, 2],
+            next: Some(s("/page/2")),
+        }
+    );
+    assert_eq!(
+        parse_with_backend::<Single<Item, String>, B>(html).unwrap(),
+        Single {
+            first: Item { id: 1 },
+            second: Some(s("2")),
+        }
+    );
+    assert_eq!(
+        parse_with_backend::<Top<u32, 2>, B>(html).unwrap(),
+        Top { items: [1, 2] }
+    );
+    assert_eq!(
+        parse_with_backend::<Borrowed<'_, u32>, B>(html).unwrap(),
+        Borrowed {
+            items: vec![1, 2],
+            label: Label(s("next").into()),
+        }
+    );
+    assert_eq!(
+        parse_with_backend::<Link<std::path::PathBuf>, B>(html).unwrap(),
+        Link {
+            href: "/page/2".into(),
+        }
+    );
+    assert_message!(
+        parse_with_backend::<Paginated<Item>, B>(html.replace("<span>2</span>", ""))
+            .unwrap_err()
+            .to_string(),
+        "items: [1] at line 4: id: mismatched number of selected elements by \"span\": expected exactly one element, but no elements found"
+    );
+}
+
+fn typed_error<B>()
+where
+    B: Backend,
+{
+    use crate::macro_utils::ProcessError;
+    use crate::transformable::VecToSingleError;
+    use std::num::IntErrorKind;
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(root = ".product")]
+    pub struct Product {
+        #[h2s(select = ".price")]
+        price: u32,
+        #[h2s(select = ".tag")]
+        tags: Vec<Tag>,
+        #[h2s(select = ".stock", parse_with = "parse_stock")]
+        in_stock: bool,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Tag(#[h2s(attr = "data-id")] u32);
+
+    fn parse_stock(s: String) -> Result<bool, std::str::ParseBoolError> {
+        s.parse()
+    }
+
+    let parse = |html: &str| parse_with_backend::<Product, B>(html).unwrap_err();
+
+    match parse(r#"<div class="product"><div class="price">free</div></div>"#) {
+        ProductError::Price(ProcessError::ParseError(e)) => {
+            assert_eq!(e.error.kind(), &IntErrorKind::InvalidDigit)
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+    match parse(r#"<div class="product"><div class="price">1</div><div class="tag"></div></div>"#) {
+        ProductError::Tags(ProcessError::ParseError(e)) => {
+            assert_eq!(e.context.0, 0);
+            assert!(matches!(
+                e.error,
+                TagError::Field0(ProcessError::ExtractionError(_))
+            ));
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+    match parse(
+        r#"<div class="product"><div class="price">1</div><div class="stock">yes</div></div>"#,
+    ) {
+        ProductError::InStock(ProcessError::ParseError(e)) => {
+            assert!(e.error.0.is::<std::str::ParseBoolError>());
+            assert_message!(
+                e.to_string(),
+                "at line 1: provided string was not `true` or `false`"
+            );
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+    match parse("<div></div>") {
+        ProductError::Root(ProcessError::TransformError(e)) => {
+            assert_eq!(e.error, VecToSingleError::NoElements)
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+    assert_message!(
+        parse(r#"<div class="product"><div class="price">1</div><div class="tag"></div></div>"#)
+            .to_string(),
+        "tags: [0] at line 1: 0: at line 1: failed to extract value of attribute=data-id: an attribute `data-id` not found in the target element",
+        "the error message is the same as before"
+    );
+}
+
+fn extractor_expression<B>()
+where
+    B: Backend,
+{
+    use crate::extraction_method::{ExtractNthText, NotFound};
+    use crate::macro_utils::ProcessError;
+
+    mod my_mod {
+        use crate::extraction_method::ExtractNthText;
+
+        pub const SECOND: ExtractNthText = ExtractNthText(1);
+    }
+
+    fn nth(n: usize) -> ExtractNthText {
+        ExtractNthText(n)
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Texts {
+        #[h2s(select = "div", extractor = nth(0))]
+        first: String,
+        #[h2s(select = "div", extractor = my_mod::SECOND)]
+        second: String,
+        #[h2s(select = "div", extractor = ExtractNthText(2), default)]
+        third: String,
+    }
+
+    assert_eq!(
+        parse_with_backend::<Texts, B>("<div>a<br>b</div>").unwrap(),
+        Texts {
+            first: s("a"),
+            second: s("b"),
+            third: s(""),
+        },
+        "any expression is accepted, and the missing value falls back to the default"
+    );
+
+    let err = parse_with_backend::<Texts, B>("<div>a</div>").unwrap_err();
+    match &err {
+        TextsError::Second(ProcessError::ExtractionError(e)) => {
+            assert!(e.error.0.is::<NotFound>(), "the original error is boxed")
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+    assert_message!(
+        err,
+        "second: at line 1: failed to extract value of ExtractNthText(1): text node of the specified number is not found"
+    );
+}
+
+fn renamed_error<B>()
+where
+    B: Backend,
+{
+    use crate::macro_utils::ProcessError;
+
+    // a user type that has the same name as the generated error type
+    #[allow(dead_code)]
+    pub struct VersionError;
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(error = "VersionParseError")]
+    pub struct Version {
+        #[h2s(select = ".v-1")]
+        v_1: u32,
+        // `v1` is `V1` as well as `v_1` without renaming
+        #[h2s(select = ".v1", error = "V1Plain")]
+        v1: u32,
+    }
+
+    let html = r#"<span class="v-1">1</span><span class="v1">x</span>"#;
+    match parse_with_backend::<Version, B>(html).unwrap_err() {
+        VersionParseError::V1Plain(ProcessError::ParseError(_)) => {}
+        e => panic!("unexpected error: {e:?}"),
+    }
+    assert_message!(
+        parse_with_backend::<Version, B>(html).unwrap_err(),
+        "v1: at line 1: invalid digit found in string",
+        "the field name is shown regardless of the variant name"
+    );
+}
+
+fn collect_errors<B>()
+where
+    B: Backend,
+{
+    use crate::path::{ErrorPaths, PathSegment};
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(collect_errors)]
+    pub struct Page {
+        #[h2s(select = "h1")]
+        title: String,
+        #[h2s(select = ".item")]
+        items: Vec<Item>,
+        #[h2s(select = ".next", attr = "href")]
+        next: Option<String>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(collect_errors)]
+    pub struct Item {
+        #[h2s(select = ".name")]
+        name: String,
+        #[h2s(select = ".price")]
+        price: u32,
+    }
+
+    assert_eq!(
+        parse_with_backend::<Page, B>(
+            r#"<h1>title</h1><div class="item"><span class="name">a</span><span class="price">1</span></div>"#
+        )
+        .unwrap(),
+        Page {
+            title: s("title"),
+            items: vec![Item {
+                name: s("a"),
+                price: 1
+            }],
+            next: None,
+        }
+    );
+
+    let err = parse_with_backend::<Page, B>(
+        r#"<div class="item"><span class="price">free</span></div><a class="next"></a><a class="next"></a>"#,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.errors.as_slice(),
+        [PageError::Title(_), PageError::Items(_), PageError::Next(_)]
+    ));
+    assert_message!(
+        err.to_string(),
+        "3 error(s) occurred: \
+         [title: mismatched number of selected elements by \"h1\": expected exactly one element, but no elements found], \
+         [items: [0] at line 1: 2 error(s) occurred: \
+         [name: mismatched number of selected elements by \".name\": expected exactly one element, but no elements found], \
+         [price: at line 1: invalid digit found in string]], \
+         [next: mismatched number of selected elements by \".next\": expected 0 or 1 element, but found 2 elements]"
+    );
+
+    let err = parse_with_backend::<Page, B>(
+        r#"<h1>title</h1>
+<div class="item"><span class="name">a</span><span class="price">free</span></div>
+<div class="item"><span class="name">b</span><span class="price">2</span></div>
+<div class="item"><span class="price">3</span></div>"#,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(
+            err.errors.as_slice(),
+            [PageError::Items(_), PageError::Items(_)]
+        ),
+        "every failing item is reported"
+    );
+    assert_message!(
+        err.to_string(),
+        "2 error(s) occurred: \
+         [items: [0] at line 2: 1 error(s) occurred: [price: at line 2: invalid digit found in string]], \
+         [items: [2] at line 4: 1 error(s) occurred: \
+         [name: mismatched number of selected elements by \".name\": expected exactly one element, but no elements found]]"
+    );
+    assert_eq!(
+        err.paths()
+            .into_iter()
+            .map(|path| path[1].clone())
+            .collect::<Vec<_>>(),
+        vec![PathSegment::Index(0), PathSegment::Index(2)]
+    );
+}
+
+fn error_paths<B>()
+where
+    B: Backend,
+{
+    use crate::path::{ErrorPaths, PathSegment};
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Page {
+        #[h2s(select = "article")]
+        articles: Vec<Article>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(collect_errors)]
+    pub struct Article {
+        #[h2s(select = "h2")]
+        title: String,
+        #[h2s(select = ".author")]
+        author: Author,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub enum Author {
+        #[h2s(when = ".anonymous")]
+        Anonymous,
+        Named(#[h2s(attr = "data-name")] String),
+    }
+
+    let field = |name: &str, selector: Option<&str>| PathSegment::Field {
+        name: name.to_string(),
+        selector: selector.map(str::to_string),
+    };
+
+    let err = parse_with_backend::<Page, B>(
+        r#"
+<article><h2>a</h2><span class="author anonymous"></span></article>
+<article><span class="author"></span></article>
+"#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.paths(),
+        vec![
+            vec![
+                field("articles", Some("article")),
+                PathSegment::Index(1),
+                field("title", Some("h2")),
+            ],
+            vec![
+                field("articles", Some("article")),
+                PathSegment::Index(1),
+                field("author", Some(".author")),
+                field("0", None),
+            ],
+        ]
+    );
+    assert_eq!(
+        <Page as FromHtml>::error_paths(&err),
+        err.paths(),
+        "the paths are also available via FromHtml"
+    );
+}
+
+fn fragment<B>()
+where
+    B: Backend,
+{
+    use crate::parse_fragment_with_backend;
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Comment {
+        #[h2s(attr = "lang")]
+        lang: String,
+        #[h2s(select = ".body")]
+        body: String,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Items {
+        #[h2s(select = "li")]
+        items: Vec<String>,
+    }
+
+    let html = r#"<div lang="en"><p class="body">hi</p></div>"#;
+    assert_eq!(
+        parse_fragment_with_backend::<Comment, B>(html).unwrap(),
+        Comment {
+            lang: s("en"),
+            body: s("hi")
+        },
+        "the root is the top-level element of the fragment"
+    );
+    assert!(
+        parse_with_backend::<Comment, B>(html).is_err(),
+        "the root of a document is <html>"
+    );
+
+    let html = r#"<li>1</li><li>2</li>"#;
+    assert_eq!(
+        parse_fragment_with_backend::<Items, B>(html).unwrap(),
+        Items {
+            items: vec![s("1"), s("2")]
+        },
+        "the root is a container of the top-level elements"
+    );
+    assert!(parse_fragment_with_backend::<Comment, B>(html).is_err());
+}
+
+fn document<B>()
+where
+    B: Backend,
+{
+    use crate::document::{Document, ExtractAllError};
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Title {
+        #[h2s(select = "h1")]
+        title: String,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Article {
+        #[h2s(select = "h2")]
+        title: String,
+        #[h2s(select = ".views")]
+        views: usize,
+    }
+
+    let doc = Document::<B>::parse(
+        r#"
+<h1>blog</h1>
+<main>
+<article><h2>a</h2><span class="views">1</span></article>
+<article><h2>b</h2><span class="views">2</span></article>
+</main>
+<aside><article><h2>c</h2><span class="views">x</span></article></aside>
+"#,
+    );
+    assert_eq!(
+        doc.extract::<Title>().unwrap(),
+        Title { title: s("blog") },
+        "the root is <html> as well as parse"
+    );
+    assert_eq!(
+        doc.extract_all::<Article>("main > article").unwrap(),
+        vec![
+            Article {
+                title: s("a"),
+                views: 1
+            },
+            Article {
+                title: s("b"),
+                views: 2
+            },
+        ],
+        "the same document can be extracted again"
+    );
+    assert!(doc.extract_all::<Title>("p").unwrap().is_empty());
+
+    let err = doc.extract_all::<Article>("article").unwrap_err();
+    assert!(matches!(&err, ExtractAllError::Item(e) if e.context.0 == 2));
+    assert_message!(
+        err,
+        "[2] at line 7: views: at line 7: invalid digit found in string",
+        "the index of the failed element is shown"
+    );
+
+    assert!(matches!(
+        doc.extract_all::<Article>("article >").unwrap_err(),
+        ExtractAllError::InvalidSelector(_)
+    ));
+
+    let html = r#"<article><h2>c</h2><span class="views">x</span></article>"#;
+    let doc = Document::<B>::parse(html).with_snippets(20);
+    assert_message!(
+        doc.extract_all::<Article>("article").unwrap_err(),
+        "[0] at line 1 (<article><h2>c</h2><...): views: at line 1 (<span class=\"views\">...): invalid digit found in string",
+        "the snippets are shown with any backend"
+    );
+    assert_message!(
+        Document::<B>::parse_fragment(html)
+            .with_snippets(20)
+            .extract::<Article>()
+            .unwrap_err(),
+        "views: at line 1 (<span class=\"views\">...): invalid digit found in string"
+    );
+    let (doc, _) = Document::<B>::parse_bytes(html.as_bytes(), None);
+    assert_message!(
+        doc.extract::<Article>().unwrap_err(),
+        "views: at line 1: invalid digit found in string",
+        "the snippets are disabled by default"
+    );
+}
+
+fn parse_all<B>()
+where
+    B: Backend,
+{
+    use crate::document::Document;
+    use crate::parse_all_with_backend;
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Item {
+        #[h2s(attr = "data-id")]
+        id: usize,
+        #[h2s(select = ".name")]
+        name: String,
+    }
+
+    let html = r#"
+<ul>
+<li data-id="1"><span class="name">a</span></li>
+<li data-id="x"><span class="name">b</span></li>
+<li data-id="3"><span class="name">c</span></li>
+<li data-id="4"></li>
+</ul>
+"#;
+    assert_eq!(
+        parse_all_with_backend::<Item, B>(
+            r#"<ul><li data-id="1"><span class="name">a</span></li><li data-id="2"><span class="name">b</span></li></ul>"#,
+            "li"
+        )
+        .unwrap(),
+        vec![
+            Item {
+                id: 1,
+                name: s("a")
+            },
+            Item {
+                id: 2,
+                name: s("b")
+            },
+        ]
+    );
+
+    let err = parse_all_with_backend::<Item, B>(html, "li").unwrap_err();
+    assert_message!(
+        err,
+        "[1] at line 4: id: at line 4: invalid digit found in string",
+        "it fails at the first broken record"
+    );
+
+    let doc = Document::<B>::parse(html);
+    let results = crate::iter::<Item, B>(&doc, "li")
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 4, "a broken record doesn't abort the others");
+    assert_eq!(
+        results[0].as_ref().unwrap(),
+        &Item {
+            id: 1,
+            name: s("a")
+        }
+    );
+    assert_eq!(results[1].as_ref().unwrap_err().context.0, 1);
+    assert_eq!(
+        results[2].as_ref().unwrap(),
+        &Item {
+            id: 3,
+            name: s("c")
+        }
+    );
+    let err = results[3].as_ref().unwrap_err();
+    assert_eq!(err.context.0, 3);
+    assert_message!(
+        err,
+        "[3] at line 6: name: mismatched number of selected elements by \".name\": expected exactly one element, but no elements found"
+    );
+
+    assert!(crate::iter::<Item, B>(&doc, "li >").is_err());
+    assert_eq!(crate::iter::<Item, B>(&doc, "p").unwrap().len(), 0);
+}
+
+fn s(s: &str) -> String {
+    s.to_string()
+}
+
+/// Removes `at line L, col C` from the message, as if the backend doesn't know the locations
+fn without_locations(message: &str) -> String {
+    let mut result = String::new();
+    let mut rest = message;
+    while let Some(i) = rest.find("at line ") {
+        let (before, after) = rest.split_at(i);
+        let after = after["at line ".len()..].trim_start_matches(|c: char| c.is_ascii_digit());
+        let after = match after.strip_prefix(", col ") {
+            Some(a) => a.trim_start_matches(|c: char| c.is_ascii_digit()),
+            None => after,
+        };
+        // `[0] at line 1: ...` is shown as `[0]: ...`, and `name: at line 1 (...)` as `name: (...)`
+        match before.strip_suffix(' ') {
+            Some(b) if !b.ends_with(':') && !b.is_empty() => {
+                result.push_str(b);
+                rest = after;
+            }
+            _ => {
+                result.push_str(before);
+                rest = after.strip_prefix(' ').unwrap_or(after);
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    backend_tests!(derive_cases);
+}
@@ -0,0 +1,334 @@
+//! A test suite that checks a backend behaves as h2s expects, which is useful to implement a custom backend.
+//!
+//! ```ignore
+//! #[test]
+//! fn conformance() {
+//!     h2s::backend::conformance::run::<MyBackend>();
+//! }
+//! ```
+//!
+//! Each check panics on failure like `assert!`, so `run` is intended to be called in a test.
+//! Since backends are free to tell the source location of elements or not, error messages are compared without it.
+
+/// Calls each of the cases with the backend `B` in scope
+macro_rules! run_cases {
+    ($($case:ident),* $(,)?) => {
+        $($case::<B>();)*
+    };
+}
+
+/// Generates a test for each of the cases, with the `Backend` in scope
+#[cfg(all(
+    test,
+    any(
+        feature = "backend-scraper",
+        feature = "backend-rcdom",
+        feature = "backend-tl"
+    )
+))]
+macro_rules! test_cases {
+    ($($case:ident),* $(,)?) => {
+        $(
+            #[test]
+            fn $case() {
+                super::super::$case::<Backend>();
+            }
+        )*
+    };
+}
+
+/// Generates a module for each backend enabled by the features, which has a test for each of the cases listed by `$cases`
+#[cfg(test)]
+macro_rules! backend_tests {
+    ($cases:ident) => {
+        #[cfg(feature = "backend-scraper")]
+        mod scraper {
+            type Backend = crate::backend::scraper::Scraper;
+            $cases!(test_cases);
+        }
+
+        #[cfg(feature = "backend-rcdom")]
+        mod rcdom {
+            type Backend = crate::backend::rcdom::RcDomBackend;
+            $cases!(test_cases);
+        }
+
+        #[cfg(feature = "backend-tl")]
+        mod tl {
+            type Backend = crate::backend::tl::Tl;
+            $cases!(test_cases);
+        }
+    };
+}
+
+/// Passes the checks of `HtmlElement` methods to the macro
+macro_rules! element_cases {
+    ($m:ident) => {
+        $m! {
+            select,
+            matches,
+            text_contents,
+            attribute,
+            child_nodes,
+            outer_html,
+            inner_html,
+            invalid_selector,
+            fragment,
+        }
+    };
+}
+
+mod derive;
+
+use h2s_core::element_selector::{Select, TargetElementSelector};
+use h2s_core::html::{Backend, CssSelector, HtmlDocument, HtmlElement, HtmlNode, TextNode};
+
+/// Runs all checks of elements and of the derived `FromHtml` implementations against the backend
+pub fn run<B>()
+where
+    B: Backend,
+{
+    run_elements::<B>();
+    run_derive::<B>();
+}
+
+/// Runs the checks of `HtmlElement` methods
+pub fn run_elements<B>()
+where
+    B: Backend,
+{
+    element_cases!(run_cases);
+}
+
+/// Runs the test cases of the derive macro
+pub fn run_derive<B>()
+where
+    B: Backend,
+{
+    derive::run::<B>();
+}
+
+fn selector<E>(selector: &str) -> E::Selector
+where
+    E: HtmlElement,
+{
+    E::Selector::parse(selector)
+        .unwrap_or_else(|e| panic!("failed to parse a valid selector {selector:?}: {e}"))
+}
+
+fn selector_of<E>(_element: &E, s: &str) -> E::Selector
+where
+    E: HtmlElement,
+{
+    selector::<E>(s)
+}
+
+fn select_by<E>(element: &E, s: &str) -> Vec<E>
+where
+    E: HtmlElement,
+{
+    element.select(&selector::<E>(s))
+}
+
+fn select<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document(
+        r#"
+<!DOCTYPE html>
+<html>
+<body>
+<div class="a">
+    <span>1</span>
+    <span>2</span>
+    <span>3</span>
+</div>
+
+<div class="b">
+    <span>4</span>
+</div>
+
+<span>5</span>
+</body>
+</html>
+        "#,
+    );
+    let root = doc.root_element();
+    assert_eq!(
+        select_by(&root, "div.a > span")
+            .iter()
+            .map(|e| e.outer_html())
+            .collect::<Vec<_>>(),
+        (1..=3)
+            .map(|s| format!("<span>{s}</span>"))
+            .collect::<Vec<_>>(),
+        "elements are selected in document order"
+    );
+    assert_eq!(select_by(&root, "span").len(), 5);
+    assert_eq!(select_by(&root, ".not-found").len(), 0);
+    assert_eq!(select_by(&root, r#"div[class="b"]"#).len(), 1);
+    assert_eq!(select_by(&root, "div[class]").len(), 2);
+
+    // nested select
+    let b = &select_by(&root, ".b")[0];
+    let b_span = select_by(b, "span");
+    assert_eq!(b_span.len(), 1);
+    assert_eq!(b_span[0].outer_html(), "<span>4</span>");
+    assert_eq!(
+        select_by(b, "div").len(),
+        0,
+        "the element itself is not selected"
+    );
+    assert_eq!(
+        select_by(b, "body span").len(),
+        1,
+        "the ancestors are taken into account in matching"
+    );
+}
+
+fn matches<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document(r#"<html><div class="a b"><p class="c"></p></div></html>"#);
+    let root = doc.root_element();
+    let div = &select_by(&root, "div")[0];
+    let matches = |s: &str| div.matches(&selector_of(div, s));
+    assert!(matches("div.a"));
+    assert!(matches(".a.b"));
+    assert!(matches("html div"));
+    // only the element itself is tested, not its descendants
+    assert!(!matches(".c"));
+    assert!(!matches("span"));
+}
+
+fn text_contents<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document("<html><div>a<div>b</div><div>c &amp; d</div></div></html>");
+    assert_eq!(
+        doc.root_element().text_contents().collect::<String>(),
+        "abc & d",
+        "the texts of descendants are concatenated in document order, and the character references are decoded"
+    );
+    let div = &select_by(&doc.root_element(), "div div")[0];
+    assert_eq!(div.text_contents().collect::<String>(), "b");
+}
+
+fn attribute<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document(
+        r#"<html><div id="foo" class="bar" title="a &lt; b" hidden></div></html>"#,
+    );
+    let elem = &select_by(&doc.root_element(), "div")[0];
+    assert_eq!(elem.attribute("id"), Some("foo"));
+    assert_eq!(elem.attribute("class"), Some("bar"));
+    assert_eq!(elem.attribute("title"), Some("a < b"));
+    assert_eq!(elem.attribute("hidden"), Some(""));
+    assert_eq!(elem.attribute("lang"), None);
+}
+
+fn child_nodes<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document("<div><div>a<div></div></div>b<div>c</div>d</div>");
+    let div = &select_by(&doc.root_element(), "div")[0];
+    assert_eq!(
+        div.child_nodes()
+            .into_iter()
+            .map(|n| match n {
+                HtmlNode::Text(text) => text.get_text(),
+                HtmlNode::Element(elem) => {
+                    format!("elem-{}", elem.text_contents().collect::<String>())
+                }
+                n => panic!("unexpected node type: {:?}", n),
+            })
+            .collect::<Vec<_>>(),
+        vec!["elem-a", "b", "elem-c", "d"],
+        "the child elements and texts are returned in document order"
+    );
+}
+
+fn outer_html<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document(r#"<html><div id="a">1<span>2</span></div></html>"#);
+    let div = &select_by(&doc.root_element(), "div")[0];
+    assert_eq!(div.outer_html(), r#"<div id="a">1<span>2</span></div>"#);
+}
+
+fn inner_html<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document(r#"<html><div id="a">1<span>2</span><br></div><p></p></html>"#);
+    let div = &select_by(&doc.root_element(), "div")[0];
+    assert_eq!(div.inner_html(), "1<span>2</span><br>");
+    let p = &select_by(&doc.root_element(), "p")[0];
+    assert_eq!(p.inner_html(), "");
+}
+
+fn invalid_selector<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_document("<html></html>");
+    let root = doc.root_element();
+    for s in [":invalid:", "div >", "[", ""] {
+        assert!(
+            <<B::Document as HtmlDocument>::Element<'_> as HtmlElement>::Selector::parse(s)
+                .is_err(),
+            "{s:?} is rejected"
+        );
+        let err = Select::new(s).select(&root).unwrap_err();
+        assert_eq!(err.selector, s);
+        assert!(
+            err.to_string().contains(&format!("{s:?}")),
+            "the error message shows the selector: {err}"
+        );
+    }
+}
+
+fn fragment<B>()
+where
+    B: Backend,
+{
+    let doc = B::parse_fragment("\n<!-- c --> <div lang=\"en\"><p>1</p><p>2</p></div>\n");
+    let root = doc.root_element();
+    assert!(
+        root.matches(&selector_of(&root, "div")),
+        "the top-level element is the root"
+    );
+    assert_eq!(root.attribute("lang"), Some("en"));
+    assert_eq!(select_by(&root, "p").len(), 2);
+    assert_eq!(select_by(&root, "div").len(), 0);
+    assert_eq!(root.text_contents().collect::<String>(), "12");
+
+    let doc = B::parse_fragment(r#"<p id="a">1</p> text <p id="b">2</p>"#);
+    let root = doc.root_element();
+    assert!(
+        !root.matches(&selector_of(&root, "p")),
+        "the root is a container if the fragment has more than one top-level node"
+    );
+    assert_eq!(root.attribute("id"), None);
+    assert_eq!(select_by(&root, "p").len(), 2);
+    assert_eq!(root.text_contents().collect::<String>(), "1 text 2");
+
+    let doc = B::parse_fragment("text only");
+    assert_eq!(
+        doc.root_element().text_contents().collect::<String>(),
+        "text only"
+    );
+}
+
+// A test for each check and backend, so that a failing check doesn't hide the others
+#[cfg(test)]
+mod test {
+    backend_tests!(element_cases);
+}
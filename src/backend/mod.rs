@@ -2,3 +2,15 @@
 
 #[cfg(feature = "backend-scraper")]
 pub mod scraper;
+
+#[cfg(feature = "backend-rcdom")]
+pub mod rcdom;
+
+#[cfg(feature = "backend-tl")]
+pub mod tl;
+
+#[cfg(any(feature = "backend-rcdom", feature = "backend-tl"))]
+pub mod selector_engine;
+
+#[cfg(any(test, feature = "conformance"))]
+pub mod conformance;
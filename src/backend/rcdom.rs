@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+use html5ever::serialize::{serialize, SerializeOpts, TraversalScope};
+use html5ever::tendril::TendrilSink;
+use html5ever::{local_name, namespace_url, ns, LocalName, QualName};
+use markup5ever_rcdom::{Handle, Node, NodeData, RcDom, SerializableHandle};
+use selectors::attr::{AttrSelectorOperation, CaseSensitivity, NamespaceConstraint};
+use selectors::matching::MatchingContext;
+use selectors::OpaqueElement;
+
+use crate::backend::selector_engine::{
+    CssString, NonTSPseudoClass, PseudoElement, Selector, Simple,
+};
+
+use h2s_core::html::{Backend, HtmlDocument, HtmlElement, HtmlNode, TextNode};
+
+/// A backend working on [`RcDom`] of html5ever, with the selector engine built on the `selectors` crate
+#[derive(Clone, Debug)]
+pub struct RcDomBackend;
+
+impl Backend for RcDomBackend {
+    type Document = RcDomDocument;
+    type Element<'a> = RcDomElement<'a>;
+    type Text<'a> = RcDomTextNode;
+
+    fn parse_document<S>(s: S) -> Self::Document
+    where
+        S: AsRef<str>,
+    {
+        let dom = html5ever::parse_document(RcDom::default(), Default::default()).one(s.as_ref());
+        parsed_document(dom)
+    }
+
+    fn parse_fragment<S>(s: S) -> Self::Document
+    where
+        S: AsRef<str>,
+    {
+        let context = QualName::new(None, ns!(html), local_name!("body"));
+        let mut document = parsed_document(
+            html5ever::parse_fragment(RcDom::default(), Default::default(), context, vec![])
+                .one(s.as_ref()),
+        );
+        // the parser puts the fragment into `<html>`, which is the synthetic container
+        if let Some(element) = single_element(&document.root) {
+            document.root = element;
+        }
+        document
+    }
+}
+
+fn parsed_document(dom: RcDom) -> RcDomDocument {
+    // html5ever always creates `<html>` even if it's omitted in the source
+    RcDomDocument::try_from(dom).expect("a parsed document must have the root element")
+}
+
+/// The only child element, if the other children are whitespaces or comments
+fn single_element(parent: &Handle) -> Option<Handle> {
+    let children = parent.children.borrow();
+    let mut nodes = children.iter().filter(|node| match &node.data {
+        NodeData::Text { contents } => !contents.borrow().trim().is_empty(),
+        NodeData::Comment { .. } => false,
+        _ => true,
+    });
+    match (nodes.next(), nodes.next()) {
+        (Some(node), None) if matches!(node.data, NodeData::Element { .. }) => Some(node.clone()),
+        _ => None,
+    }
+}
+
+/// An [`RcDom`] that can be parsed into a struct.
+/// An existing DOM can be used by converting it with `TryFrom`, which fails if the DOM has no element
+pub struct RcDomDocument {
+    dom: RcDom,
+    /// `<html>`, or the top-level element of a fragment
+    root: Handle,
+    /// Attributes and texts copied from the `RefCell`s of the DOM, so that they can be borrowed as `&str`
+    strings: HashMap<*const Node, NodeStrings>,
+}
+
+enum NodeStrings {
+    Attributes(Vec<(LocalName, String)>),
+    Text(String),
+}
+
+impl TryFrom<RcDom> for RcDomDocument {
+    type Error = NoRootElement;
+
+    fn try_from(dom: RcDom) -> Result<Self, Self::Error> {
+        let mut strings = HashMap::new();
+        let mut stack = vec![dom.document.clone()];
+        while let Some(node) = stack.pop() {
+            match &node.data {
+                NodeData::Element { attrs, .. } => {
+                    let attrs = attrs
+                        .borrow()
+                        .iter()
+                        .map(|attr| (attr.name.local.clone(), attr.value.to_string()))
+                        .collect();
+                    strings.insert(Rc::as_ptr(&node), NodeStrings::Attributes(attrs));
+                }
+                NodeData::Text { contents } => {
+                    let text = contents.borrow().to_string();
+                    strings.insert(Rc::as_ptr(&node), NodeStrings::Text(text));
+                }
+                _ => {}
+            }
+            stack.extend(node.children.borrow().iter().cloned());
+        }
+        let root = dom
+            .document
+            .children
+            .borrow()
+            .iter()
+            .find(|node| matches!(node.data, NodeData::Element { .. }))
+            .cloned()
+            .ok_or(NoRootElement)?;
+        Ok(RcDomDocument { dom, root, strings })
+    }
+}
+
+/// The DOM has no element to be the root, e.g. `RcDom::default()`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NoRootElement;
+
+impl Error for NoRootElement {}
+
+impl Display for NoRootElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the document has no root element")
+    }
+}
+
+impl Debug for RcDomDocument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RcDomDocument")
+            .field(&self.dom.document)
+            .finish()
+    }
+}
+
+impl HtmlDocument for RcDomDocument {
+    type Element<'a> = RcDomElement<'a>;
+
+    fn root_element(&self) -> Self::Element<'_> {
+        RcDomElement {
+            node: self.root.clone(),
+            document: self,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RcDomTextNode(Handle);
+
+impl TextNode for RcDomTextNode {
+    fn get_text(&self) -> String {
+        match &self.0.data {
+            NodeData::Text { contents } => contents.borrow().to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// An element node and the document it belongs to
+#[derive(Clone)]
+pub struct RcDomElement<'a> {
+    node: Handle,
+    document: &'a RcDomDocument,
+}
+
+impl<'a> Debug for RcDomElement<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RcDomElement").field(&self.node).finish()
+    }
+}
+
+impl<'a> RcDomElement<'a> {
+    fn wrap(&self, node: Handle) -> Option<Self> {
+        matches!(node.data, NodeData::Element { .. }).then_some(RcDomElement {
+            node,
+            document: self.document,
+        })
+    }
+
+    fn name(&self) -> &html5ever::QualName {
+        match &self.node.data {
+            NodeData::Element { name, .. } => name,
+            _ => unreachable!("RcDomElement always wraps an element node"),
+        }
+    }
+
+    fn attributes(&self) -> &'a [(LocalName, String)] {
+        match self.document.strings.get(&Rc::as_ptr(&self.node)) {
+            Some(NodeStrings::Attributes(attrs)) => attrs,
+            _ => &[],
+        }
+    }
+
+    fn parent_node(&self) -> Option<Handle> {
+        let parent = self.node.parent.take();
+        self.node.parent.set(parent.clone());
+        parent.and_then(|weak| weak.upgrade())
+    }
+
+    /// Element siblings before and after this element
+    fn sibling_elements(&self) -> (Option<Self>, Option<Self>) {
+        let Some(parent) = self.parent_node() else {
+            return (None, None);
+        };
+        let children = parent.children.borrow();
+        let Some(index) = children.iter().position(|c| Rc::ptr_eq(c, &self.node)) else {
+            return (None, None);
+        };
+        let prev = children[..index]
+            .iter()
+            .rev()
+            .find_map(|c| self.wrap(c.clone()));
+        let next = children[index + 1..]
+            .iter()
+            .find_map(|c| self.wrap(c.clone()));
+        (prev, next)
+    }
+
+    /// All descendant nodes in document order, not including the element itself
+    fn descendants(&self) -> Vec<Handle> {
+        let mut descendants = vec![];
+        let mut stack = self
+            .node
+            .children
+            .borrow()
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<Vec<_>>();
+        while let Some(node) = stack.pop() {
+            stack.extend(node.children.borrow().iter().rev().cloned());
+            descendants.push(node);
+        }
+        descendants
+    }
+
+    fn serialize(&self, traversal_scope: TraversalScope) -> String {
+        let opts = SerializeOpts {
+            scripting_enabled: false,
+            traversal_scope,
+            create_missing_parent: false,
+        };
+        let mut buf = Vec::new();
+        serialize(&mut buf, &SerializableHandle::from(self.node.clone()), opts)
+            .expect("writing to Vec never fails");
+        String::from_utf8(buf).expect("html5ever serializes to UTF-8")
+    }
+}
+
+impl<'a> HtmlElement for RcDomElement<'a> {
+    type Backend = RcDomBackend;
+    type Selector = Selector;
+    type TextContents<'b>
+        = std::vec::IntoIter<&'b str>
+    where
+        Self: 'b;
+
+    fn select(&self, selector: &Self::Selector) -> Vec<Self> {
+        self.descendants()
+            .into_iter()
+            .filter_map(|node| self.wrap(node))
+            .filter(|e| selector.matches(e, Some(self)))
+            .collect()
+    }
+
+    fn matches(&self, selector: &Self::Selector) -> bool {
+        selector.matches(self, None)
+    }
+
+    fn text_contents(&self) -> Self::TextContents<'_> {
+        self.descendants()
+            .iter()
+            .filter_map(|node| match self.document.strings.get(&Rc::as_ptr(node)) {
+                Some(NodeStrings::Text(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn attribute<S>(&self, attr: S) -> Option<&str>
+    where
+        S: AsRef<str>,
+    {
+        self.attributes()
+            .iter()
+            .find(|(name, _)| name.as_ref() == attr.as_ref())
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child_nodes(&self) -> Vec<HtmlNode<'_, Self::Backend>> {
+        self.node
+            .children
+            .borrow()
+            .iter()
+            .map(|node| match node.data {
+                NodeData::Element { .. } => HtmlNode::Element(RcDomElement {
+                    node: node.clone(),
+                    document: self.document,
+                }),
+                NodeData::Text { .. } => HtmlNode::Text(RcDomTextNode(node.clone())),
+                NodeData::Document
+                | NodeData::Doctype { .. }
+                | NodeData::Comment { .. }
+                | NodeData::ProcessingInstruction { .. } => HtmlNode::Other,
+            })
+            .collect()
+    }
+
+    fn outer_html(&self) -> String {
+        self.serialize(TraversalScope::IncludeNode)
+    }
+
+    fn inner_html(&self) -> String {
+        self.serialize(TraversalScope::ChildrenOnly(None))
+    }
+}
+
+/// Note: never matches non-tree-structural pseudo-classes, the same as the scraper backend
+impl<'a> selectors::Element for RcDomElement<'a> {
+    type Impl = Simple;
+
+    fn opaque(&self) -> OpaqueElement {
+        OpaqueElement::new(&*self.node)
+    }
+
+    fn parent_element(&self) -> Option<Self> {
+        self.parent_node().and_then(|parent| self.wrap(parent))
+    }
+
+    fn parent_node_is_shadow_root(&self) -> bool {
+        false
+    }
+
+    fn containing_shadow_host(&self) -> Option<Self> {
+        None
+    }
+
+    fn is_pseudo_element(&self) -> bool {
+        false
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.sibling_elements().0
+    }
+
+    fn next_sibling_element(&self) -> Option<Self> {
+        self.sibling_elements().1
+    }
+
+    fn is_html_element_in_html_document(&self) -> bool {
+        self.name().ns == ns!(html)
+    }
+
+    fn has_local_name(&self, local_name: &CssString) -> bool {
+        *self.name().local == *local_name.0
+    }
+
+    fn has_namespace(&self, ns: &CssString) -> bool {
+        *self.name().ns == *ns.0
+    }
+
+    fn is_same_type(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+
+    fn attr_matches(
+        &self,
+        ns: &NamespaceConstraint<&CssString>,
+        local_name: &CssString,
+        operation: &AttrSelectorOperation<&CssString>,
+    ) -> bool {
+        match &self.node.data {
+            NodeData::Element { attrs, .. } => attrs.borrow().iter().any(|attr| {
+                !matches!(*ns, NamespaceConstraint::Specific(url) if *url.0 != *attr.name.ns)
+                    && *local_name.0 == *attr.name.local
+                    && operation.eval_str(&attr.value)
+            }),
+            _ => false,
+        }
+    }
+
+    fn match_non_ts_pseudo_class<F>(
+        &self,
+        _pc: &NonTSPseudoClass,
+        _context: &mut MatchingContext<Self::Impl>,
+        _flags_setter: &mut F,
+    ) -> bool {
+        false
+    }
+
+    fn match_pseudo_element(
+        &self,
+        _pe: &PseudoElement,
+        _context: &mut MatchingContext<Self::Impl>,
+    ) -> bool {
+        false
+    }
+
+    fn is_link(&self) -> bool {
+        self.name().local.as_ref() == "link"
+    }
+
+    fn is_html_slot_element(&self) -> bool {
+        true
+    }
+
+    fn has_id(&self, id: &CssString, case_sensitivity: CaseSensitivity) -> bool {
+        self.attribute("id").map_or(false, |v| {
+            case_sensitivity.eq(id.0.as_bytes(), v.as_bytes())
+        })
+    }
+
+    fn has_class(&self, name: &CssString, case_sensitivity: CaseSensitivity) -> bool {
+        self.attribute("class").map_or(false, |classes| {
+            classes
+                .split_ascii_whitespace()
+                .any(|class| case_sensitivity.eq(name.0.as_bytes(), class.as_bytes()))
+        })
+    }
+
+    fn imported_part(&self, _name: &CssString) -> Option<CssString> {
+        None
+    }
+
+    fn is_part(&self, _name: &CssString) -> bool {
+        false
+    }
+
+    fn is_empty(&self) -> bool {
+        !self
+            .node
+            .children
+            .borrow()
+            .iter()
+            .any(|child| matches!(child.data, NodeData::Element { .. } | NodeData::Text { .. }))
+    }
+
+    fn is_root(&self) -> bool {
+        self.parent_node()
+            .map_or(false, |parent| matches!(parent.data, NodeData::Document))
+    }
+}
+
+// The test cases applicable to any backend are in `conformance`
+#[cfg(test)]
+mod test {
+    use super::*;
+    use h2s_core::html::CssSelector;
+
+    fn select<'a>(element: &RcDomElement<'a>, selector: &str) -> Vec<RcDomElement<'a>> {
+        element.select(&CssSelector::parse(selector).unwrap())
+    }
+
+    #[test]
+    fn existing_dom() {
+        let dom = html5ever::parse_document(RcDom::default(), Default::default())
+            .one(r#"<p class="x">hello</p>"#);
+        let doc = RcDomDocument::try_from(dom).unwrap();
+        let p = &select(&doc.root_element(), "p.x")[0];
+        assert_eq!(p.text_contents().collect::<String>(), "hello");
+
+        assert_eq!(
+            RcDomDocument::try_from(RcDom::default()).unwrap_err(),
+            NoRootElement
+        );
+    }
+}
@@ -1,9 +1,17 @@
+use ego_tree::NodeId;
+use html5ever::tendril::{StrTendril, TendrilSink};
+use html5ever::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
+use html5ever::{driver, local_name, namespace_url, ns, Attribute, ExpandedName, QualName};
 use scraper::{ElementRef, Html, Node, Selector};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::error::Error;
 
-use h2s_core::html::{Backend, CssSelector, HtmlDocument, HtmlElement, HtmlNode, TextNode};
+use h2s_core::html::{
+    Backend, CssSelector, HtmlDocument, HtmlElement, HtmlNode, SourceSpan, TextNode,
+};
 use scraper::node::Text;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 
 #[derive(Clone, Debug)]
 pub struct Scraper;
@@ -17,18 +25,63 @@ impl Backend for Scraper {
     where
         S: AsRef<str>,
     {
-        ScraperDocument(Html::parse_document(s.as_ref()))
+        let sink = LineTrackingSink {
+            html: Html::new_document(),
+            current_line: 1,
+            lines: HashMap::new(),
+        };
+        let sink = driver::parse_document(sink, Default::default()).one(s.as_ref());
+        let root = sink.html.root_element().id();
+        ScraperDocument(sink.html, sink.lines, root)
+    }
+
+    fn parse_fragment<S>(s: S) -> Self::Document
+    where
+        S: AsRef<str>,
+    {
+        let sink = LineTrackingSink {
+            html: Html::new_fragment(),
+            current_line: 1,
+            lines: HashMap::new(),
+        };
+        let context = QualName::new(None, ns!(html), local_name!("body"));
+        let sink =
+            driver::parse_fragment(sink, Default::default(), context, vec![]).one(s.as_ref());
+        // the parser puts the fragment into `<html>`, which is the synthetic container
+        let container = sink.html.root_element();
+        let root = single_element(container).unwrap_or(container).id();
+        ScraperDocument(sink.html, sink.lines, root)
     }
 }
 
+/// The only child element, if the other children are whitespaces or comments
+fn single_element(parent: ElementRef) -> Option<ElementRef> {
+    let mut elements = parent.children().filter(|node| match node.value() {
+        Node::Text(text) => !text.trim().is_empty(),
+        Node::Comment(_) => false,
+        _ => true,
+    });
+    match (elements.next(), elements.next()) {
+        (Some(node), None) => ElementRef::wrap(node),
+        _ => None,
+    }
+}
+
+/// A parsed HTML, the line numbers of its elements, and the root element
 #[derive(Clone, Debug)]
-pub struct ScraperDocument(Html);
+pub struct ScraperDocument(Html, HashMap<NodeId, usize>, NodeId);
 
 impl HtmlDocument for ScraperDocument {
     type Element<'a> = ScraperHtmlElement<'a>;
 
     fn root_element(&self) -> Self::Element<'_> {
-        ScraperHtmlElement(self.0.root_element())
+        let root = self
+            .0
+            .tree
+            .get(self.2)
+            .and_then(ElementRef::wrap)
+            .expect("the root must be an element in the document");
+        ScraperHtmlElement(root, &self.1)
     }
 }
 
@@ -41,8 +94,15 @@ impl<'a> TextNode for ScraperTextNode<'a> {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct ScraperHtmlElement<'a>(ElementRef<'a>);
+/// An element and the line numbers of all elements in the document
+#[derive(Clone)]
+pub struct ScraperHtmlElement<'a>(ElementRef<'a>, &'a HashMap<NodeId, usize>);
+
+impl<'a> Debug for ScraperHtmlElement<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ScraperHtmlElement").field(&self.0).finish()
+    }
+}
 
 impl<'a> HtmlElement for ScraperHtmlElement<'a> {
     type Backend = Scraper;
@@ -52,7 +112,14 @@ impl<'a> HtmlElement for ScraperHtmlElement<'a> {
         Self:'b;
 
     fn select(&self, selector: &Self::Selector) -> Vec<Self> {
-        self.0.select(&selector.0).map(ScraperHtmlElement).collect()
+        self.0
+            .select(&selector.0)
+            .map(|e| ScraperHtmlElement(e, self.1))
+            .collect()
+    }
+
+    fn matches(&self, selector: &Self::Selector) -> bool {
+        selector.0.matches(&self.0)
     }
 
     fn text_contents(&self) -> Self::TextContents<'a> {
@@ -71,7 +138,7 @@ impl<'a> HtmlElement for ScraperHtmlElement<'a> {
             .children()
             .map(|node| match node.value() {
                 Node::Element(_) => {
-                    HtmlNode::Element(ScraperHtmlElement(ElementRef::wrap(node).unwrap()))
+                    HtmlNode::Element(ScraperHtmlElement(ElementRef::wrap(node).unwrap(), self.1))
                 }
                 Node::Text(text) => HtmlNode::Text(ScraperTextNode(text)),
                 // TODO
@@ -83,6 +150,131 @@ impl<'a> HtmlElement for ScraperHtmlElement<'a> {
             })
             .collect::<Vec<_>>()
     }
+
+    fn source_span(&self) -> Option<SourceSpan> {
+        // html5ever only tells the line number
+        self.1.get(&self.0.id()).map(|&line| SourceSpan {
+            line,
+            column: None,
+            byte_range: None,
+        })
+    }
+
+    fn outer_html(&self) -> String {
+        self.0.html()
+    }
+
+    fn inner_html(&self) -> String {
+        self.0.inner_html()
+    }
+}
+
+/// A tree sink that records the line number where each element is created,
+/// since `Html` itself drops the line numbers notified by the parser
+struct LineTrackingSink {
+    html: Html,
+    current_line: usize,
+    lines: HashMap<NodeId, usize>,
+}
+
+impl TreeSink for LineTrackingSink {
+    type Output = Self;
+    type Handle = NodeId;
+
+    fn finish(self) -> Self {
+        self
+    }
+
+    fn parse_error(&mut self, msg: Cow<'static, str>) {
+        self.html.parse_error(msg)
+    }
+
+    fn get_document(&mut self) -> Self::Handle {
+        self.html.get_document()
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a Self::Handle) -> ExpandedName<'a> {
+        self.html.elem_name(target)
+    }
+
+    fn create_element(
+        &mut self,
+        name: QualName,
+        attrs: Vec<Attribute>,
+        flags: ElementFlags,
+    ) -> Self::Handle {
+        let id = self.html.create_element(name, attrs, flags);
+        self.lines.insert(id, self.current_line);
+        id
+    }
+
+    fn create_comment(&mut self, text: StrTendril) -> Self::Handle {
+        self.html.create_comment(text)
+    }
+
+    fn create_pi(&mut self, target: StrTendril, data: StrTendril) -> Self::Handle {
+        self.html.create_pi(target, data)
+    }
+
+    fn append(&mut self, parent: &Self::Handle, child: NodeOrText<Self::Handle>) {
+        self.html.append(parent, child)
+    }
+
+    fn append_based_on_parent_node(
+        &mut self,
+        element: &Self::Handle,
+        prev_element: &Self::Handle,
+        child: NodeOrText<Self::Handle>,
+    ) {
+        self.html
+            .append_based_on_parent_node(element, prev_element, child)
+    }
+
+    fn append_doctype_to_document(
+        &mut self,
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
+    ) {
+        self.html
+            .append_doctype_to_document(name, public_id, system_id)
+    }
+
+    fn get_template_contents(&mut self, target: &Self::Handle) -> Self::Handle {
+        self.html.get_template_contents(target)
+    }
+
+    fn same_node(&self, x: &Self::Handle, y: &Self::Handle) -> bool {
+        self.html.same_node(x, y)
+    }
+
+    fn set_quirks_mode(&mut self, mode: QuirksMode) {
+        self.html.set_quirks_mode(mode)
+    }
+
+    fn append_before_sibling(
+        &mut self,
+        sibling: &Self::Handle,
+        new_node: NodeOrText<Self::Handle>,
+    ) {
+        self.html.append_before_sibling(sibling, new_node)
+    }
+
+    fn add_attrs_if_missing(&mut self, target: &Self::Handle, attrs: Vec<Attribute>) {
+        self.html.add_attrs_if_missing(target, attrs)
+    }
+
+    fn remove_from_parent(&mut self, target: &Self::Handle) {
+        self.html.remove_from_parent(target)
+    }
+
+    fn reparent_children(&mut self, node: &Self::Handle, new_parent: &Self::Handle) {
+        self.html.reparent_children(node, new_parent)
+    }
+
+    fn set_current_line(&mut self, line_number: u64) {
+        self.current_line = line_number as usize;
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -113,7 +305,7 @@ impl Display for ParseFailed {
     }
 }
 
-// TODO generify test cases that can be applied to any backend
+// The test cases applicable to any backend are in `conformance`
 #[cfg(test)]
 mod test {
     use super::*;
@@ -130,88 +322,98 @@ mod test {
     }
 
     #[test]
-    fn select() {
+    fn source_span() {
         let doc = Scraper::parse_document(
-            r#"
-<!DOCTYPE html>
-<html>
-<body>
-<div class="a">
-    <span>1</span>
-    <span>2</span>
-    <span>3</span>
-</div>
-
-<div class="b">
-    <span>4</span>
-</div>
-
-<span>5</span>
-</body>
-</html>
-        "#,
+            "<html>\n<body>\n<div>\n  <span>1</span>\n</div></body></html>",
         );
-        let a_span = doc
-            .root_element()
-            .select(&CssSelector::parse("div.a > span").unwrap());
-        assert_eq!(
-            a_span.iter().map(|e| e.0.html()).collect::<Vec<_>>(),
-            (1..=3)
-                .map(|s| format!("<span>{s}</span>"))
-                .collect::<Vec<_>>(),
-        );
-
-        // nested select
-        let b = &doc
-            .root_element()
-            .select(&CssSelector::parse(".b").unwrap())[0];
-        let b_span = b.select(&CssSelector::parse("span").unwrap());
-        assert_eq!(b_span.len(), 1);
-        assert_eq!(b_span[0].0.html(), "<span>4</span>".to_string());
+        let line = |selector: &str| {
+            doc.root_element()
+                .select(&CssSelector::parse(selector).unwrap())[0]
+                .source_span()
+                .map(|span| span.line)
+        };
+        assert_eq!(line("div"), Some(3));
+        assert_eq!(line("span"), Some(4));
     }
 
     #[test]
-    fn text_contents() {
-        let doc = Scraper::parse_document("<html><div>a<div>b</div><div>c</div></div></html>");
-        assert_eq!(
-            doc.root_element().text_contents().collect::<Vec<_>>(),
-            vec!["a", "b", "c"]
+    fn default_parse_fragment() {
+        /// A backend that doesn't implement `parse_fragment`
+        #[derive(Debug)]
+        struct DocumentOnly;
+
+        impl Backend for DocumentOnly {
+            type Document = ScraperDocument;
+            type Element<'a> = ScraperHtmlElement<'a>;
+            type Text<'a> = ScraperTextNode<'a>;
+
+            fn parse_document<S>(s: S) -> Self::Document
+            where
+                S: AsRef<str>,
+            {
+                Scraper::parse_document(s)
+            }
+        }
+
+        let doc = DocumentOnly::parse_fragment("<p>a</p>");
+        let root = doc.root_element();
+        assert!(
+            root.matches(&CssSelector::parse("html").unwrap()),
+            "the fragment is parsed as a document"
         );
+        assert_eq!(root.text_contents().collect::<String>(), "a");
     }
 
     #[test]
-    fn get_attribute() {
-        let doc = Scraper::parse_document(r#"<html><div id="foo" class="bar" /></html>"#);
-        let elem = doc
-            .root_element()
-            .select(&CssSelector::parse("div").unwrap())[0]
-            .clone();
-        assert_eq!(elem.attribute("id").unwrap(), "foo");
-        assert_eq!(elem.attribute("class").unwrap(), "bar");
-    }
+    fn default_inner_html() {
+        /// An element that doesn't implement `inner_html`
+        #[derive(Debug, Clone)]
+        struct OuterOnly<'a>(ScraperHtmlElement<'a>);
 
-    #[test]
-    fn child_nodes() {
-        let doc = Scraper::parse_document("<div><div>a<div></div></div>b<div>c</div>d</div>");
-        println!("{:#?}", &doc.0.tree);
+        impl<'a> HtmlElement for OuterOnly<'a> {
+            type Backend = Scraper;
+            type Selector = ScraperCssSelector;
+            type TextContents<'b>
+                = scraper::element_ref::Text<'b>
+            where
+                Self: 'b;
+
+            fn select(&self, selector: &Self::Selector) -> Vec<Self> {
+                self.0.select(selector).into_iter().map(OuterOnly).collect()
+            }
+
+            fn matches(&self, selector: &Self::Selector) -> bool {
+                self.0.matches(selector)
+            }
+
+            fn text_contents(&self) -> Self::TextContents<'_> {
+                self.0.text_contents()
+            }
+
+            fn attribute<S>(&self, attr: S) -> Option<&str>
+            where
+                S: AsRef<str>,
+            {
+                self.0.attribute(attr)
+            }
+
+            fn child_nodes(&self) -> Vec<HtmlNode<'_, Self::Backend>> {
+                self.0.child_nodes()
+            }
+
+            fn outer_html(&self) -> String {
+                self.0.outer_html()
+            }
+        }
+
+        let doc = Scraper::parse_document(
+            r#"<html><div>1 &lt; 2<!-- c --><span class="a">&amp;</span><br></div></html>"#,
+        );
+        let div =
+            OuterOnly(doc.root_element()).select(&CssSelector::parse("div").unwrap())[0].clone();
         assert_eq!(
-            doc.root_element()
-                .select(&CssSelector::parse("div").unwrap())[0]
-                .child_nodes()
-                .into_iter()
-                .map(|n| match n {
-                    HtmlNode::Text(text) => text.get_text(),
-                    HtmlNode::Element(elem) => format!(
-                        "elem-{}",
-                        elem.text_contents().fold("".to_string(), |a, b| a + b)
-                    ),
-                    _ => panic!("unexpected node type: {:?}", n),
-                })
-                .collect::<Vec<_>>(),
-            vec!["elem-a", "b", "elem-c", "d"]
-                .into_iter()
-                .map(|a| a.to_string())
-                .collect::<Vec<_>>()
+            div.inner_html(),
+            r#"1 &lt; 2<span class="a">&amp;</span><br>"#
         );
     }
 }
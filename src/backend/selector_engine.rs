@@ -0,0 +1,143 @@
+//! A CSS selector engine built on the `selectors` crate,
+//! shared by the backends whose parser has no complete selector implementation.
+//! A backend uses it by implementing `selectors::Element` for its element type.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use cssparser::ToCss;
+use selectors::matching::{MatchingContext, MatchingMode, QuirksMode};
+use selectors::parser::{SelectorList, SelectorParseErrorKind};
+
+use h2s_core::html::CssSelector;
+
+#[derive(Debug, Clone)]
+pub struct Selector(SelectorList<Simple>);
+
+impl Selector {
+    /// Whether the element matches the selector, where `:scope` is the `scope` element if specified
+    pub(crate) fn matches<E>(&self, element: &E, scope: Option<&E>) -> bool
+    where
+        E: selectors::Element<Impl = Simple>,
+    {
+        let mut context =
+            MatchingContext::new(MatchingMode::Normal, None, None, QuirksMode::NoQuirks);
+        context.scope_element = scope.map(|e| e.opaque());
+        selectors::matching::matches_selector_list(&self.0, element, &mut context)
+    }
+}
+
+impl CssSelector for Selector {
+    type Error = ParseFailed;
+
+    fn parse<S>(s: S) -> Result<Self, ParseFailed>
+    where
+        S: AsRef<str>,
+    {
+        let mut input = cssparser::ParserInput::new(s.as_ref());
+        SelectorList::parse(&SelectorParser, &mut cssparser::Parser::new(&mut input))
+            .map(Selector)
+            .map_err(|e| ParseFailed(format!("{:?}", e.kind)))
+    }
+}
+
+/// A failure of parsing a css selector, with the reason reported by the `selectors` crate
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseFailed(pub String);
+
+impl Error for ParseFailed {}
+
+impl Display for ParseFailed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to parse css selector: {}", self.0)
+    }
+}
+
+struct SelectorParser;
+
+impl<'i> selectors::parser::Parser<'i> for SelectorParser {
+    type Impl = Simple;
+    type Error = SelectorParseErrorKind<'i>;
+}
+
+/// A `SelectorImpl` with no pseudo-classes or pseudo-elements.
+/// Namespaces aren't distinguished since not all backends know them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Simple;
+
+impl selectors::parser::SelectorImpl for Simple {
+    type ExtraMatchingData = ();
+    type AttrValue = CssString;
+    type Identifier = CssString;
+    type LocalName = CssString;
+    type NamespaceUrl = CssString;
+    type NamespacePrefix = CssString;
+    type BorrowedNamespaceUrl = CssString;
+    type BorrowedLocalName = CssString;
+    type NonTSPseudoClass = NonTSPseudoClass;
+    type PseudoElement = PseudoElement;
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CssString(pub String);
+
+impl<'a> From<&'a str> for CssString {
+    fn from(s: &'a str) -> Self {
+        CssString(s.to_string())
+    }
+}
+
+impl AsRef<str> for CssString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ToCss for CssString {
+    fn to_css<W>(&self, dest: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        cssparser::serialize_identifier(&self.0, dest)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonTSPseudoClass {}
+
+impl selectors::parser::NonTSPseudoClass for NonTSPseudoClass {
+    type Impl = Simple;
+
+    fn is_active_or_hover(&self) -> bool {
+        match *self {}
+    }
+
+    fn is_user_action_state(&self) -> bool {
+        match *self {}
+    }
+}
+
+impl ToCss for NonTSPseudoClass {
+    fn to_css<W>(&self, _dest: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match *self {}
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PseudoElement {}
+
+impl selectors::parser::PseudoElement for PseudoElement {
+    type Impl = Simple;
+}
+
+impl ToCss for PseudoElement {
+    fn to_css<W>(&self, _dest: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match *self {}
+    }
+}
@@ -0,0 +1,597 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+use selectors::attr::{AttrSelectorOperation, CaseSensitivity, NamespaceConstraint};
+use selectors::matching::MatchingContext;
+use selectors::OpaqueElement;
+use tl::{HTMLTag, Node, NodeHandle, VDom, VDomGuard};
+
+use h2s_core::html::{Backend, HtmlDocument, HtmlElement, HtmlNode, SourceSpan, TextNode};
+
+use crate::backend::selector_engine::{
+    CssString, NonTSPseudoClass, PseudoElement, Selector, Simple,
+};
+
+/// A backend working on the `tl` parser, which is much faster than html5ever-based ones
+/// since it doesn't build a spec-compliant tree.
+/// For example, omitted tags like `<html>` or `<tbody>` aren't complemented.
+/// The selector engine is built on the `selectors` crate since the one of `tl` supports only a few selectors
+#[derive(Clone, Debug)]
+pub struct Tl;
+
+impl Backend for Tl {
+    type Document = TlDocument;
+    type Element<'a> = TlElement<'a>;
+    type Text<'a> = TlTextNode<'a>;
+
+    fn parse_document<S>(s: S) -> Self::Document
+    where
+        S: AsRef<str>,
+    {
+        let mut document = TlDocument::new(s.as_ref().to_string());
+        document.root = document.vdom().children().iter().copied().find(|handle| {
+            matches!(document.node(*handle), Node::Tag(tag) if tag.name().as_bytes().eq_ignore_ascii_case(b"html"))
+        });
+        document
+    }
+
+    fn parse_fragment<S>(s: S) -> Self::Document
+    where
+        S: AsRef<str>,
+    {
+        // `tl` doesn't complement the omitted tags, so a fragment is parsed in the same way as a document
+        let mut document = TlDocument::new(s.as_ref().to_string());
+        let mut nodes =
+            document.vdom().children().iter().copied().filter(|handle| {
+                match document.node(*handle) {
+                    Node::Raw(text) => !text.as_utf8_str().trim().is_empty(),
+                    Node::Comment(_) => false,
+                    Node::Tag(_) => true,
+                }
+            });
+        document.root = match (nodes.next(), nodes.next()) {
+            (Some(handle), None) if matches!(document.node(handle), Node::Tag(_)) => Some(handle),
+            _ => None,
+        };
+        document
+    }
+}
+
+impl TlDocument {
+    /// Parses the source, where the root is the virtual one
+    fn new(source: String) -> Self {
+        let mut lines = vec![];
+        let mut start = 0;
+        for line in source.split('\n') {
+            lines.push(Line {
+                start,
+                ascii: line.is_ascii(),
+            });
+            start += line.len() + 1;
+        }
+        // SAFETY: `tl` documents that it can't cause UB in its current form.
+        // The DOM borrows the source moved into the guard, and the guard is never mutated
+        let dom = unsafe { tl::parse_owned(source, Default::default()) }
+            // `tl` fails only when the input is longer than `u32::MAX` bytes
+            .expect("the input HTML is too large to parse by tl");
+
+        let vdom = dom.get_ref();
+        let mut parents = vec![None; vdom.nodes().len()];
+        let mut decoded_texts = HashMap::new();
+        let mut decoded_attributes = HashMap::new();
+        for (i, node) in vdom.nodes().iter().enumerate() {
+            match node {
+                Node::Tag(tag) => {
+                    for child in tag.children().top().as_slice() {
+                        parents[child.get_inner() as usize] = Some(NodeHandle::new(i as u32));
+                    }
+                    for (name, value) in tag.attributes().iter() {
+                        if let Some(value) = value.as_deref().and_then(decode_character_references)
+                        {
+                            decoded_attributes.insert((i as u32, name.to_ascii_lowercase()), value);
+                        }
+                    }
+                }
+                Node::Raw(text) => {
+                    if let Some(text) = decode_character_references(&text.as_utf8_str()) {
+                        decoded_texts.insert(i as u32, text);
+                    }
+                }
+                Node::Comment(_) => {}
+            }
+        }
+
+        TlDocument {
+            dom,
+            root: None,
+            parents,
+            lines,
+            decoded_texts,
+            decoded_attributes,
+        }
+    }
+}
+
+/// A DOM parsed by `tl` and the indices to traverse it like the other backends
+pub struct TlDocument {
+    dom: VDomGuard,
+    /// `<html>`, or the top-level element of a fragment. `None` is the virtual root whose children are the top-level nodes
+    root: Option<NodeHandle>,
+    /// The parent of each node, since `tl` has links only from parents to children
+    parents: Vec<Option<NodeHandle>>,
+    /// The lines of the source, to find where elements are located
+    lines: Vec<Line>,
+    /// Texts and attribute values with character references decoded, since `tl` keeps them as is
+    decoded_texts: HashMap<u32, String>,
+    decoded_attributes: HashMap<(u32, String), String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    /// Byte offset where the line starts
+    start: usize,
+    /// Whether the line has only ASCII characters, where the column number is the same as the byte offset.
+    /// `tl` doesn't provide the source after parsing, so columns in non-ASCII lines are unknown
+    ascii: bool,
+}
+
+impl Debug for TlDocument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TlDocument")
+            .field(&self.vdom().outer_html())
+            .finish()
+    }
+}
+
+impl TlDocument {
+    fn vdom(&self) -> &VDom<'_> {
+        self.dom.get_ref()
+    }
+
+    fn node(&self, handle: NodeHandle) -> &Node<'_> {
+        handle
+            .get(self.vdom().parser())
+            .expect("a node handle must be valid in its document")
+    }
+
+    fn text(&self, handle: NodeHandle) -> Option<&str> {
+        match self.node(handle) {
+            Node::Raw(text) => Some(
+                self.decoded_texts
+                    .get(&handle.get_inner())
+                    .map(|s| s.as_str())
+                    .unwrap_or_else(|| text.try_as_utf8_str().unwrap_or_default()),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl HtmlDocument for TlDocument {
+    type Element<'a> = TlElement<'a>;
+
+    fn root_element(&self) -> Self::Element<'_> {
+        TlElement {
+            node: self.root,
+            document: self,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TlTextNode<'a>(&'a str);
+
+impl<'a> TextNode for TlTextNode<'a> {
+    fn get_text(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// An element node and the document it belongs to.
+/// The node is `None` for the document root if the document has no `<html>` element,
+/// where all the top-level nodes are its children
+#[derive(Clone)]
+pub struct TlElement<'a> {
+    node: Option<NodeHandle>,
+    document: &'a TlDocument,
+}
+
+impl<'a> Debug for TlElement<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TlElement")
+            .field(&self.tag().map(|tag| tag.name().as_utf8_str()))
+            .finish()
+    }
+}
+
+impl<'a> TlElement<'a> {
+    fn wrap(&self, handle: NodeHandle) -> Option<Self> {
+        matches!(self.document.node(handle), Node::Tag(_)).then_some(TlElement {
+            node: Some(handle),
+            document: self.document,
+        })
+    }
+
+    fn tag(&self) -> Option<&'a HTMLTag<'a>> {
+        self.node
+            .and_then(|handle| self.document.node(handle).as_tag())
+    }
+
+    fn with_children<R>(&self, f: impl FnOnce(&[NodeHandle]) -> R) -> R {
+        match self.tag() {
+            Some(tag) => f(tag.children().top().as_slice()),
+            None => f(self.document.vdom().children()),
+        }
+    }
+
+    /// The parent element, where `None` means that the element is at the top level
+    fn parent(&self) -> Option<Self> {
+        let handle = self.node?;
+        self.document.parents[handle.get_inner() as usize].and_then(|parent| self.wrap(parent))
+    }
+
+    /// Element siblings before and after this element
+    fn sibling_elements(&self) -> (Option<Self>, Option<Self>) {
+        let Some(handle) = self.node else {
+            return (None, None);
+        };
+        // the top-level nodes are the children of the document root
+        let parent = self.parent().unwrap_or(TlElement {
+            node: None,
+            document: self.document,
+        });
+        parent.with_children(|siblings| {
+            let Some(index) = siblings.iter().position(|s| *s == handle) else {
+                return (None, None);
+            };
+            let prev = siblings[..index].iter().rev().find_map(|s| self.wrap(*s));
+            let next = siblings[index + 1..].iter().find_map(|s| self.wrap(*s));
+            (prev, next)
+        })
+    }
+
+    /// All descendant nodes in document order, not including the element itself
+    fn descendants(&self) -> Vec<NodeHandle> {
+        let mut descendants = vec![];
+        let mut stack = self.with_children(|c| c.iter().rev().copied().collect::<Vec<_>>());
+        while let Some(handle) = stack.pop() {
+            if let Node::Tag(tag) = self.document.node(handle) {
+                stack.extend(tag.children().top().as_slice().iter().rev().copied());
+            }
+            descendants.push(handle);
+        }
+        descendants
+    }
+
+    fn name(&self) -> Cow<'a, str> {
+        self.tag()
+            .map(|tag| tag.name().as_utf8_str())
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> HtmlElement for TlElement<'a> {
+    type Backend = Tl;
+    type Selector = Selector;
+    type TextContents<'b>
+        = std::vec::IntoIter<&'b str>
+    where
+        Self: 'b;
+
+    fn select(&self, selector: &Self::Selector) -> Vec<Self> {
+        self.descendants()
+            .into_iter()
+            .filter_map(|handle| self.wrap(handle))
+            .filter(|e| selector.matches(e, Some(self)))
+            .collect()
+    }
+
+    fn matches(&self, selector: &Self::Selector) -> bool {
+        self.node.is_some() && selector.matches(self, None)
+    }
+
+    fn text_contents(&self) -> Self::TextContents<'_> {
+        self.descendants()
+            .into_iter()
+            .filter_map(|handle| self.document.text(handle))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn attribute<S>(&self, attr: S) -> Option<&str>
+    where
+        S: AsRef<str>,
+    {
+        let handle = self.node?;
+        let attr = attr.as_ref().to_ascii_lowercase();
+        if let Some(decoded) = self
+            .document
+            .decoded_attributes
+            .get(&(handle.get_inner(), attr.clone()))
+        {
+            return Some(decoded);
+        }
+        let attributes = self.tag()?.attributes();
+        let value = match attr.as_str() {
+            "id" => attributes.id().map(Some),
+            "class" => attributes.class().map(Some),
+            // `Attributes::get` can't be used since its result is bound to the lifetime of the key
+            _ => attributes
+                .unstable_raw()
+                .iter()
+                .find(|(name, _)| name.as_bytes().eq_ignore_ascii_case(attr.as_bytes()))
+                .map(|(_, value)| value.as_ref()),
+        }?;
+        Some(value.and_then(|v| v.try_as_utf8_str()).unwrap_or_default())
+    }
+
+    fn child_nodes(&self) -> Vec<HtmlNode<'_, Self::Backend>> {
+        self.with_children(|children| {
+            children
+                .iter()
+                .map(|handle| match self.document.node(*handle) {
+                    Node::Tag(_) => HtmlNode::Element(TlElement {
+                        node: Some(*handle),
+                        document: self.document,
+                    }),
+                    Node::Raw(_) => {
+                        HtmlNode::Text(TlTextNode(self.document.text(*handle).unwrap_or_default()))
+                    }
+                    Node::Comment(_) => HtmlNode::Other,
+                })
+                .collect()
+        })
+    }
+
+    fn source_span(&self) -> Option<SourceSpan> {
+        let (start, end) = self.tag()?.boundaries(self.document.vdom().parser());
+        let lines = &self.document.lines;
+        let line = lines.partition_point(|l| l.start <= start);
+        let Line {
+            start: line_start,
+            ascii,
+        } = lines[line - 1];
+        Some(SourceSpan {
+            line,
+            column: ascii.then_some(start - line_start + 1),
+            byte_range: Some(start..end + 1),
+        })
+    }
+
+    fn outer_html(&self) -> String {
+        match self.tag() {
+            Some(tag) => tag.outer_html(self.document.vdom().parser()),
+            None => self.document.vdom().outer_html(),
+        }
+    }
+
+    fn inner_html(&self) -> String {
+        match self.tag() {
+            Some(tag) => tag.inner_html(self.document.vdom().parser()),
+            // the virtual root has no tag of its own
+            None => self.document.vdom().outer_html(),
+        }
+    }
+}
+
+/// Note: never matches non-tree-structural pseudo-classes, the same as the other backends
+impl<'a> selectors::Element for TlElement<'a> {
+    type Impl = Simple;
+
+    fn opaque(&self) -> OpaqueElement {
+        match self.tag() {
+            Some(tag) => OpaqueElement::new(tag),
+            None => OpaqueElement::new(self.document),
+        }
+    }
+
+    fn parent_element(&self) -> Option<Self> {
+        self.parent()
+    }
+
+    fn parent_node_is_shadow_root(&self) -> bool {
+        false
+    }
+
+    fn containing_shadow_host(&self) -> Option<Self> {
+        None
+    }
+
+    fn is_pseudo_element(&self) -> bool {
+        false
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.sibling_elements().0
+    }
+
+    fn next_sibling_element(&self) -> Option<Self> {
+        self.sibling_elements().1
+    }
+
+    fn is_html_element_in_html_document(&self) -> bool {
+        true
+    }
+
+    fn has_local_name(&self, local_name: &CssString) -> bool {
+        // `tl` keeps the case of tag names as is
+        self.name().eq_ignore_ascii_case(&local_name.0)
+    }
+
+    fn has_namespace(&self, _ns: &CssString) -> bool {
+        // `tl` doesn't know namespaces
+        false
+    }
+
+    fn is_same_type(&self, other: &Self) -> bool {
+        self.name().eq_ignore_ascii_case(&other.name())
+    }
+
+    fn attr_matches(
+        &self,
+        ns: &NamespaceConstraint<&CssString>,
+        local_name: &CssString,
+        operation: &AttrSelectorOperation<&CssString>,
+    ) -> bool {
+        // attributes without namespace prefix are specified as the empty namespace
+        !matches!(ns, NamespaceConstraint::Specific(url) if !url.0.is_empty())
+            && self
+                .attribute(&local_name.0)
+                .map_or(false, |value| operation.eval_str(value))
+    }
+
+    fn match_non_ts_pseudo_class<F>(
+        &self,
+        _pc: &NonTSPseudoClass,
+        _context: &mut MatchingContext<Self::Impl>,
+        _flags_setter: &mut F,
+    ) -> bool {
+        false
+    }
+
+    fn match_pseudo_element(
+        &self,
+        _pe: &PseudoElement,
+        _context: &mut MatchingContext<Self::Impl>,
+    ) -> bool {
+        false
+    }
+
+    fn is_link(&self) -> bool {
+        self.name().eq_ignore_ascii_case("link")
+    }
+
+    fn is_html_slot_element(&self) -> bool {
+        true
+    }
+
+    fn has_id(&self, id: &CssString, case_sensitivity: CaseSensitivity) -> bool {
+        self.attribute("id").map_or(false, |v| {
+            case_sensitivity.eq(id.0.as_bytes(), v.as_bytes())
+        })
+    }
+
+    fn has_class(&self, name: &CssString, case_sensitivity: CaseSensitivity) -> bool {
+        self.attribute("class").map_or(false, |classes| {
+            classes
+                .split_ascii_whitespace()
+                .any(|class| case_sensitivity.eq(name.0.as_bytes(), class.as_bytes()))
+        })
+    }
+
+    fn imported_part(&self, _name: &CssString) -> Option<CssString> {
+        None
+    }
+
+    fn is_part(&self, _name: &CssString) -> bool {
+        false
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.with_children(|children| {
+            children
+                .iter()
+                .any(|child| matches!(self.document.node(*child), Node::Tag(_) | Node::Raw(_)))
+        })
+    }
+
+    fn is_root(&self) -> bool {
+        self.node.is_some() && self.parent().is_none()
+    }
+}
+
+/// Decodes numeric character references and the basic named ones,
+/// or returns `None` if the text has nothing to decode
+fn decode_character_references(text: &str) -> Option<String> {
+    if !text.contains('&') {
+        return None;
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let c = reference.and_then(|reference| match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => {
+                let code = reference.strip_prefix('#')?;
+                let code = match code.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => code.parse(),
+                };
+                code.ok().and_then(char::from_u32)
+            }
+        });
+        match (c, reference) {
+            (Some(c), Some(reference)) => {
+                decoded.push(c);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Some(decoded)
+}
+
+// The test cases applicable to any backend are in `conformance`
+#[cfg(test)]
+mod test {
+    use super::*;
+    use h2s_core::html::CssSelector;
+
+    fn select<'a>(element: &TlElement<'a>, selector: &str) -> Vec<TlElement<'a>> {
+        element.select(&CssSelector::parse(selector).unwrap())
+    }
+
+    #[test]
+    fn without_html_element() {
+        let doc = Tl::parse_document(r#"<p id="a">1</p><p id="b">2</p>"#);
+        let root = doc.root_element();
+        assert_eq!(select(&root, "p").len(), 2);
+        assert_eq!(select(&root, "p:first-child")[0].attribute("id"), Some("a"));
+        assert_eq!(select(&root, "p + p")[0].attribute("id"), Some("b"));
+        assert_eq!(root.text_contents().collect::<String>(), "12");
+        assert!(!root.matches(&CssSelector::parse("*").unwrap()));
+    }
+
+    #[test]
+    fn source_span() {
+        let doc =
+            Tl::parse_document("<html>\n<body>\n<div>\n  <span>1</span>\n</div></body></html>");
+        let span = |selector: &str| select(&doc.root_element(), selector)[0].source_span();
+        assert_eq!(
+            span("span"),
+            Some(SourceSpan {
+                line: 4,
+                column: Some(3),
+                byte_range: Some(22..36),
+            })
+        );
+        assert_eq!(span("div").map(|s| s.line), Some(3));
+
+        // columns of non-ASCII lines are unknown
+        let doc = Tl::parse_document("<html><p>あ</p><b>1</b></html>");
+        let b = &select(&doc.root_element(), "b")[0];
+        assert_eq!(b.source_span().map(|s| (s.line, s.column)), Some((1, None)));
+    }
+
+    #[test]
+    fn character_references() {
+        assert_eq!(decode_character_references("abc"), None);
+        assert_eq!(
+            decode_character_references("&lt;a&gt; &#x41;&#66; &unknown; & &amp"),
+            Some("<a> AB &unknown; & &amp".to_string())
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! A parsed HTML document, from which any number of structs can be extracted
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::iter::Enumerate;
+use std::marker::PhantomData;
+
+use h2s_core::element_selector::{InvalidSelector, Select, TargetElementSelector};
+use h2s_core::encoding_rs::Encoding;
+use h2s_core::html::{Backend, HtmlDocument, HtmlElement, WithSnippet};
+use h2s_core::macro_utils::ParseError;
+use h2s_core::traversable_with_context::ListIndex;
+use h2s_core::FromHtml;
+
+/// An HTML document parsed by the backend `B`.
+/// It's useful to extract several structs from the same HTML without parsing it again
+pub struct Document<B>
+where
+    B: Backend,
+{
+    document: B::Document,
+    /// The max length of the HTML snippets in errors, which are disabled if `None`
+    snippet_max_len: Option<usize>,
+}
+
+impl<B> Document<B>
+where
+    B: Backend,
+{
+    /// Wraps a document already parsed by the backend
+    pub fn new(document: B::Document) -> Self {
+        Self {
+            document,
+            snippet_max_len: None,
+        }
+    }
+
+    pub fn parse(html: impl AsRef<str>) -> Self {
+        Self::new(B::parse_document(html))
+    }
+
+    /// Parses HTML as a fragment, whose top-level element is the root
+    pub fn parse_fragment(html: impl AsRef<str>) -> Self {
+        Self::new(B::parse_fragment(html))
+    }
+
+    /// Parses HTML bytes, and returns the detected encoding together
+    pub fn parse_bytes(html: &[u8], hint: Option<&'static Encoding>) -> (Self, &'static Encoding) {
+        let (document, encoding) = B::parse_document_bytes(html, hint);
+        (Self::new(document), encoding)
+    }
+
+    /// Makes the errors of the extracted structs include an HTML snippet of the offending element truncated to `max_len` bytes.
+    /// It's opt-in since the snippets make error messages long
+    pub fn with_snippets(self, max_len: usize) -> Self {
+        Self {
+            snippet_max_len: Some(max_len),
+            ..self
+        }
+    }
+
+    pub fn root_element(&self) -> <B::Document as HtmlDocument>::Element<'_> {
+        self.document.root_element()
+    }
+
+    /// Extracts a struct from the root element
+    pub fn extract<T>(&self) -> Result<T, T::Error>
+    where
+        T: FromHtml,
+    {
+        from_html(self.root_element(), self.snippet_max_len)
+    }
+
+    /// Extracts a struct from each element selected by the css selector, in document order.
+    /// It fails at the first element that fails to extract
+    pub fn extract_all<T>(&self, selector: &str) -> Result<Vec<T>, ExtractAllError<T::Error>>
+    where
+        T: FromHtml,
+    {
+        self.iter(selector)
+            .map_err(ExtractAllError::InvalidSelector)?
+            .map(|result| result.map_err(ExtractAllError::Item))
+            .collect()
+    }
+
+    /// Iterates over the structs extracted from each element selected by the css selector.
+    /// Each element is extracted lazily, and its failure doesn't stop the iteration
+    pub fn iter<T>(&self, selector: &str) -> Result<Iter<'_, T, B>, InvalidSelector>
+    where
+        T: FromHtml,
+    {
+        let elements = Select::new(selector).select(&self.root_element())?;
+        Ok(Iter {
+            elements: elements.into_iter().enumerate(),
+            snippet_max_len: self.snippet_max_len,
+            _item: PhantomData,
+        })
+    }
+
+    pub fn into_inner(self) -> B::Document {
+        self.document
+    }
+}
+
+/// Extracts a struct from the element, which shows its snippet in errors if `snippet_max_len` is given
+fn from_html<T, E>(element: E, snippet_max_len: Option<usize>) -> Result<T, T::Error>
+where
+    T: FromHtml,
+    E: HtmlElement,
+{
+    match snippet_max_len {
+        Some(max_len) => T::from_html(WithSnippet::new(element, max_len)),
+        None => T::from_html(element),
+    }
+}
+
+/// An iterator over the structs extracted from the selected elements, created by `Document::iter`
+pub struct Iter<'a, T, B>
+where
+    B: Backend + 'a,
+{
+    elements: Enumerate<std::vec::IntoIter<<B::Document as HtmlDocument>::Element<'a>>>,
+    snippet_max_len: Option<usize>,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<'a, T, B> Iterator for Iter<'a, T, B>
+where
+    T: FromHtml,
+    B: Backend + 'a,
+{
+    /// The error has the index of the element among the selected elements
+    type Item = Result<T, ParseError<ListIndex, T::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, element) = self.elements.next()?;
+        let span = element.source_span();
+        let snippet = self
+            .snippet_max_len
+            .and_then(|max_len| WithSnippet::new(element.clone(), max_len).error_snippet());
+        Some(
+            from_html(element, self.snippet_max_len).map_err(|error| ParseError {
+                context: ListIndex(i),
+                span,
+                snippet,
+                error,
+            }),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.elements.size_hint()
+    }
+}
+
+impl<'a, T, B> ExactSizeIterator for Iter<'a, T, B>
+where
+    T: FromHtml,
+    B: Backend + 'a,
+{
+}
+
+impl<B> Debug for Document<B>
+where
+    B: Backend,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Document")
+            .field("document", &self.document)
+            .field("snippet_max_len", &self.snippet_max_len)
+            .finish()
+    }
+}
+
+/// An error of extracting structs from the selected elements
+#[derive(Debug)]
+pub enum ExtractAllError<E>
+where
+    E: Error,
+{
+    /// The backend failed to parse the css selector
+    InvalidSelector(InvalidSelector),
+    /// An element failed to extract, with its index among the selected elements
+    Item(ParseError<ListIndex, E>),
+}
+
+impl<E> Display for ExtractAllError<E>
+where
+    E: Error,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractAllError::InvalidSelector(e) => write!(f, "{e}"),
+            ExtractAllError::Item(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E> Error for ExtractAllError<E> where E: Error {}
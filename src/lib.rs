@@ -99,7 +99,7 @@
 //! let err = h2s::parse::<Page>(invalid_html).unwrap_err();
 //! assert_eq!(
 //!   err.to_string(),
-//!   "articles: [2]: title: mismatched number of selected elements by \"h2 > a\": expected exactly one element, but no elements found"
+//!   "articles: [2] at line 17: title: mismatched number of selected elements by \"h2 > a\": expected exactly one element, but no elements found"
 //! );
 //! ```
 //!
@@ -113,20 +113,58 @@
 //!   - Numeric types ( `usize`, `i64`, `NonZeroU32`, ... )
 //!   - And more built-in supported types ([List](./core/src/parseable.rs))
 //!   - Or you can use any types by implementing yourself ([Example](./examples/custom_field_value.rs))
+//!   - Unit-only enums by deriving `ParseableEnum`
 //!
 //! ## Container types (where `T` is a basic type)
 //!
 //!   - `[T;N]`
 //!   - `Option<T>`
 //!   - `Vec<T>`
+//!
+//! # Inputs
+//!
+//!   - `parse`: a whole HTML document
+//!   - `parse_fragment`: an HTML fragment like `<div>...</div>`, whose top-level element is the root
+//!   - `parse_bytes`: HTML bytes in any encoding like Shift_JIS, which is detected from the BOM, `<meta>`, or a hint, and reported back
+//!
+//! To extract several structs from one HTML without parsing it again, keep the parsed `Document`,
+//! and call `extract` for the root element or `extract_all` for each element selected by a css selector.
+//!
+//! For a list of records, `parse_all` extracts each element selected by a css selector without a wrapper struct,
+//! and `iter` yields the result of each element, so that a broken record doesn't abort the others.
+//!
+//! # Backends
+//!
+//! The HTML parser is selected by cargo features, and used by `parse_with_backend`.
+//!
+//!   - `backend-scraper` (default): [scraper](https://crates.io/crates/scraper), used by `parse`
+//!   - `backend-rcdom`: html5ever's [RcDom](https://crates.io/crates/markup5ever_rcdom), which can also wrap an existing DOM
+//!   - `backend-tl`: [tl](https://crates.io/crates/tl), which is much faster but doesn't build a spec-compliant tree ([Benchmark](./benches/backends.rs))
+//!
+//! With the `streaming` feature, `streaming::stream_items` reads the items of a `Vec` field one by one from `std::io::Read`, without building the whole DOM.
+//!
+//! A custom backend can be implemented by the traits in `h2s::html`, and checked by `backend::conformance::run` with the `conformance` feature.
 
-use h2s_core::html::{Backend, HtmlDocument};
+use h2s_core::element_selector::InvalidSelector;
+use h2s_core::encoding_rs::Encoding;
+use h2s_core::html::Backend;
 pub use h2s_core::*;
 pub use h2s_macro::*;
 
+pub use crate::document::Document;
+use crate::document::{ExtractAllError, Iter};
+
+#[cfg(feature = "backend-scraper")]
 use crate::backend::scraper::Scraper;
 
+// The code generated by the derive macro refers to `::h2s`, which is also used in this crate by the conformance suite
+#[cfg(any(test, feature = "conformance"))]
+extern crate self as h2s;
+
 pub mod backend;
+pub mod document;
+#[cfg(feature = "streaming")]
+pub mod streaming;
 
 /// A shorthand method without specifying backend HTML parser
 #[cfg(feature = "backend-scraper")]
@@ -138,11 +176,109 @@ where
     parse_with_backend::<T, Scraper>(html)
 }
 
+/// Same as `parse`, but errors include an HTML snippet of the offending element truncated to `max_len` bytes.
+/// It's opt-in since the snippets make error messages long.
+/// For the other backends and ways of parsing, use `Document::with_snippets`
+#[cfg(feature = "backend-scraper")]
+pub fn parse_with_snippets<T>(html: impl AsRef<str>, max_len: usize) -> Result<T, T::Error>
+where
+    T: FromHtml,
+{
+    Document::<Scraper>::parse(html)
+        .with_snippets(max_len)
+        .extract()
+}
+
 /// Parsing with specific backend HTML parser
 pub fn parse_with_backend<T, B>(html: impl AsRef<str>) -> Result<T, T::Error>
 where
     T: FromHtml,
     B: Backend,
 {
-    T::from_html(B::parse_document(html).root_element())
+    Document::<B>::parse(html).extract()
+}
+
+/// Same as `parse`, but the HTML is given as bytes in any encoding, which is detected from the BOM, `<meta>`, and `hint` in this order.
+/// The detected encoding is returned together, even if the parsing fails
+#[cfg(feature = "backend-scraper")]
+pub fn parse_bytes<T>(
+    html: &[u8],
+    hint: Option<&'static Encoding>,
+) -> (Result<T, T::Error>, &'static Encoding)
+where
+    T: FromHtml,
+{
+    parse_bytes_with_backend::<T, Scraper>(html, hint)
+}
+
+/// Parsing bytes with specific backend HTML parser
+pub fn parse_bytes_with_backend<T, B>(
+    html: &[u8],
+    hint: Option<&'static Encoding>,
+) -> (Result<T, T::Error>, &'static Encoding)
+where
+    T: FromHtml,
+    B: Backend,
+{
+    let (document, encoding) = Document::<B>::parse_bytes(html, hint);
+    (document.extract(), encoding)
+}
+
+/// Same as `parse`, but the HTML is parsed as a fragment like `<div>...</div>`, not as a whole document.
+/// The top-level element of the fragment is the root of the struct,
+/// or a synthetic container of the top-level nodes if the fragment has more than one
+#[cfg(feature = "backend-scraper")]
+pub fn parse_fragment<T>(html: impl AsRef<str>) -> Result<T, T::Error>
+where
+    T: FromHtml,
+{
+    parse_fragment_with_backend::<T, Scraper>(html)
+}
+
+/// Parsing a fragment with specific backend HTML parser
+pub fn parse_fragment_with_backend<T, B>(html: impl AsRef<str>) -> Result<T, T::Error>
+where
+    T: FromHtml,
+    B: Backend,
+{
+    Document::<B>::parse_fragment(html).extract()
+}
+
+/// Same as `parse`, but a struct is extracted from each element selected by the css selector,
+/// without declaring a wrapper struct of the `Vec` field.
+/// It fails at the first element that fails to extract, whose index is shown in the error
+#[cfg(feature = "backend-scraper")]
+pub fn parse_all<T>(
+    html: impl AsRef<str>,
+    selector: &str,
+) -> Result<Vec<T>, ExtractAllError<T::Error>>
+where
+    T: FromHtml,
+{
+    parse_all_with_backend::<T, Scraper>(html, selector)
+}
+
+/// Parsing all elements with specific backend HTML parser
+pub fn parse_all_with_backend<T, B>(
+    html: impl AsRef<str>,
+    selector: &str,
+) -> Result<Vec<T>, ExtractAllError<T::Error>>
+where
+    T: FromHtml,
+    B: Backend,
+{
+    Document::<B>::parse(html).extract_all(selector)
+}
+
+/// Iterates over the structs extracted from each element of the document selected by the css selector.
+/// Each item is the result of each element, so that a broken element doesn't abort the others
+pub fn iter<'a, T, B>(
+    document: &'a Document<B>,
+    selector: &str,
+) -> Result<Iter<'a, T, B>, InvalidSelector>
+where
+    T: FromHtml,
+    B: Backend,
+{
+    document.iter(selector)
 }
@@ -0,0 +1,441 @@
+//! Streaming extraction for HTML too large to build its whole DOM, such as multi-hundred-MB exports.
+//!
+//! The selector of a `Vec` field of a struct is compiled into a handler of [lol_html],
+//! and each selected element is emitted as an item as soon as its end tag is reached.
+//! Only the HTML of the items being read is kept in memory,
+//! and it's parsed into the item type by the default backend.
+//!
+//! The omitted end tags, like the ones of `<li>` and `<p>`, are complemented by the start tag of the following sibling
+//! or the end tag of the ancestor, in a simplified way of the HTML parser.
+//!
+//! Since lol_html reads HTML forward only, the selector can't need lookahead,
+//! like the sibling combinators `+` and `~`, `:last-child` or `:has()`.
+//! The fields of the item type are selected within the item, so they don't have such restrictions.
+//!
+//! [lol_html]: https://crates.io/crates/lol_html
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use lol_html::errors::{RewritingError, SelectorError};
+use lol_html::html_content::Element;
+use lol_html::{DocumentContentHandlers, ElementContentHandlers, HtmlRewriter, Selector, Settings};
+
+use h2s_core::html::{Backend, CssSelector, HtmlDocument, HtmlElement};
+use h2s_core::FromHtml;
+
+use crate::backend::scraper::Scraper;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The rewriter used only for reading, whose output is discarded
+type Rewriter = HtmlRewriter<'static, fn(&[u8])>;
+
+/// Reads the HTML from `reader`, and iterates over the items of the `Vec` field named `field` of `T`.
+/// It fails if the field can't be streamed, or its item type isn't `I`
+pub fn stream_items<T, I, R>(reader: R, field: &str) -> Result<StreamItems<I, R>, SchemaError>
+where
+    T: FromHtml,
+    I: FromHtml,
+    R: Read,
+{
+    let schema = T::schema().ok_or(SchemaError::NoSchema)?;
+    let field_schema = schema
+        .field(field)
+        .ok_or_else(|| SchemaError::UnknownField(field.to_string()))?;
+    if !field_schema.is_vec {
+        return Err(SchemaError::NotVec(field.to_string()));
+    }
+    if field_schema.item_type != std::any::type_name::<I>() {
+        return Err(SchemaError::ItemTypeMismatch {
+            field: field.to_string(),
+            item_type: std::any::type_name::<I>(),
+        });
+    }
+    let selector = field_schema
+        .selector
+        .ok_or_else(|| SchemaError::NoSelector(field.to_string()))?;
+    let selector = match schema.root {
+        // a selector list can't be simply concatenated
+        Some(root) if root.contains(',') || selector.contains(',') => {
+            return Err(SchemaError::UnsupportedSelector {
+                field: field.to_string(),
+                selector: format!("{root} {selector}"),
+                reason: "a selector list can't be combined with `root`".to_string(),
+            })
+        }
+        Some(root) => format!("{root} {selector}"),
+        None => selector.to_string(),
+    };
+    let item_selector =
+        Selector::from_str(&selector).map_err(|e| SchemaError::UnsupportedSelector {
+            field: field.to_string(),
+            selector: selector.clone(),
+            reason: unsupported_reason(e),
+        })?;
+
+    let state = Rc::new(RefCell::new(CaptureState::default()));
+    let settings = Settings {
+        // the handlers for the same element run in this order
+        element_content_handlers: vec![
+            (
+                Cow::Owned(item_selector),
+                ElementContentHandlers::default().element({
+                    let state = state.clone();
+                    move |_| {
+                        state.borrow_mut().item_starts = true;
+                        Ok(())
+                    }
+                }),
+            ),
+            (
+                Cow::Owned(Selector::from_str("*").expect("the universal selector is supported")),
+                ElementContentHandlers::default().element({
+                    let state = state.clone();
+                    move |element| {
+                        capture_element(&state, element);
+                        Ok(())
+                    }
+                }),
+            ),
+        ],
+        document_content_handlers: vec![DocumentContentHandlers::default()
+            .text({
+                let state = state.clone();
+                move |text| {
+                    state.borrow_mut().push_str(text.as_str());
+                    Ok(())
+                }
+            })
+            .end({
+                let state = state.clone();
+                move |_| {
+                    // the items without end tag, which are completed by the end of the document
+                    state.borrow_mut().close_to(0);
+                    Ok(())
+                }
+            })],
+        ..Settings::default()
+    };
+
+    Ok(StreamItems {
+        reader,
+        rewriter: Some(HtmlRewriter::new(settings, |_: &[u8]| {})),
+        state,
+        index: 0,
+        _item: PhantomData,
+    })
+}
+
+/// An iterator over the items parsed from HTML stream.
+/// Items are emitted in the order of their end tags including the omitted ones, so a nested item comes before its ancestor item
+pub struct StreamItems<I, R> {
+    reader: R,
+    /// `None` if the stream has ended
+    rewriter: Option<Rewriter>,
+    state: Rc<RefCell<CaptureState>>,
+    index: usize,
+    _item: PhantomData<fn() -> I>,
+}
+
+impl<I, R> Iterator for StreamItems<I, R>
+where
+    I: FromHtml,
+    R: Read,
+{
+    type Item = Result<I, StreamError<I::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0; CHUNK_SIZE];
+        loop {
+            let completed = self.state.borrow_mut().completed.pop_front();
+            if let Some(html) = completed {
+                let index = self.index;
+                self.index += 1;
+                return Some(
+                    parse_item(&html)
+                        .ok_or(StreamError::NoElement { index })
+                        .and_then(|result| {
+                            result.map_err(|error| StreamError::Item { index, error })
+                        }),
+                );
+            }
+
+            let rewriter = self.rewriter.as_mut()?;
+            let result = match self.reader.read(&mut buf) {
+                Ok(0) => self.rewriter.take().map_or(Ok(()), |r| r.end()),
+                Ok(n) => rewriter.write(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.rewriter = None;
+                    return Some(Err(StreamError::Io(e)));
+                }
+            };
+            if let Err(e) = result {
+                self.rewriter = None;
+                return Some(Err(StreamError::Rewriting(e)));
+            }
+        }
+    }
+}
+
+fn parse_item<I>(html: &str) -> Option<Result<I, I::Error>>
+where
+    I: FromHtml,
+{
+    let document = Scraper::parse_document(html);
+    let selector = CssSelector::parse("body > *").expect("the selector is valid");
+    // the parser puts the item into the body of a complemented document
+    let item = document
+        .root_element()
+        .select(&selector)
+        .into_iter()
+        .next()?;
+    Some(I::from_html(item))
+}
+
+/// The HTML of the items being read
+#[derive(Default)]
+struct CaptureState {
+    /// Whether the next element is an item, which is notified by the handler of the item selector
+    item_starts: bool,
+    /// The elements whose end tag isn't reached yet, from outer to inner. Void elements are excluded
+    open_elements: Vec<OpenElement>,
+    /// The items whose end tag isn't reached yet, from outer to inner,
+    /// with the position of the item element in `open_elements`
+    open_items: Vec<(usize, String)>,
+    completed: VecDeque<String>,
+    next_id: usize,
+}
+
+struct OpenElement {
+    /// Identifies the element in its end tag handler, since it may have been closed implicitly
+    id: usize,
+    name: String,
+}
+
+impl CaptureState {
+    fn push_str(&mut self, html: &str) {
+        for (_, item) in &mut self.open_items {
+            item.push_str(html);
+        }
+    }
+
+    /// Closes the open elements at `depth` and deeper from inner to outer, and completes the items among them
+    fn close_to(&mut self, depth: usize) {
+        while self.open_elements.len() > depth {
+            let element = self.open_elements.pop().expect("an element is open");
+            self.push_str(&format!("</{}>", element.name));
+            if matches!(self.open_items.last(), Some((d, _)) if *d == self.open_elements.len()) {
+                let (_, item) = self.open_items.pop().expect("the item is open");
+                self.completed.push_back(item);
+            }
+        }
+    }
+}
+
+fn capture_element(state: &Rc<RefCell<CaptureState>>, element: &mut Element) {
+    let mut s = state.borrow_mut();
+    let name = element.tag_name().to_ascii_lowercase();
+    if let Some(depth) = implicitly_closed(&s.open_elements, &name) {
+        s.close_to(depth);
+    }
+    let depth = s.open_elements.len();
+    let is_item = std::mem::take(&mut s.item_starts);
+    if is_item {
+        s.open_items.push((depth, String::new()));
+    }
+
+    if !s.open_items.is_empty() {
+        let mut start_tag = format!("<{name}");
+        for attr in element.attributes() {
+            // the raw value is kept, so that the character references are decoded by parsing again
+            start_tag.push_str(&format!(
+                " {}=\"{}\"",
+                attr.name(),
+                attr.value().replace('"', "&quot;")
+            ));
+        }
+        start_tag.push('>');
+        s.push_str(&start_tag);
+    }
+
+    match element.end_tag_handlers() {
+        Some(handlers) => {
+            let id = s.next_id;
+            s.next_id += 1;
+            s.open_elements.push(OpenElement { id, name });
+            let state = state.clone();
+            handlers.push(Box::new(move |_| {
+                let mut s = state.borrow_mut();
+                // nothing to do if it's already closed by the following sibling or the ancestor
+                if let Some(depth) = s.open_elements.iter().rposition(|e| e.id == id) {
+                    s.close_to(depth);
+                }
+                Ok(())
+            }));
+        }
+        // a void element like `<img>` is completed by its start tag
+        None if is_item => {
+            let (_, item) = s.open_items.pop().expect("the item is open");
+            s.completed.push_back(item);
+        }
+        None => {}
+    }
+}
+
+/// The elements whose start tag closes an open `<p>`
+const CLOSING_P: [&str; 30] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// The position of the open element that the start tag of `name` closes implicitly,
+/// e.g. `<li>` closes the previous `<li>` in the same list
+fn implicitly_closed(open_elements: &[OpenElement], name: &str) -> Option<usize> {
+    // the closed elements are searched from inner to outer until one of the boundaries
+    let (closed, boundaries): (&[&str], &[&str]) = match name {
+        "li" => (&["li"], &["ul", "ol", "menu"]),
+        "dt" | "dd" => (&["dt", "dd"], &["dl"]),
+        "tr" => (&["tr"], &["table", "thead", "tbody", "tfoot"]),
+        "td" | "th" => (&["td", "th"], &["tr", "table"]),
+        "thead" | "tbody" | "tfoot" => (&["thead", "tbody", "tfoot"], &["table"]),
+        "option" => (&["option"], &["select", "datalist", "optgroup"]),
+        "optgroup" => (&["optgroup", "option"], &["select"]),
+        name if CLOSING_P.contains(&name) => (&["p"], &["button", "table", "td", "th"]),
+        _ => return None,
+    };
+    open_elements
+        .iter()
+        .enumerate()
+        .rev()
+        .take_while(|(_, e)| !boundaries.contains(&e.name.as_str()))
+        .filter(|(_, e)| closed.contains(&e.name.as_str()))
+        .map(|(i, _)| i)
+        .last()
+}
+
+fn unsupported_reason(e: SelectorError) -> String {
+    match e {
+        SelectorError::UnsupportedCombinator(c) => {
+            format!("the combinator `{c}` needs lookahead, which is unavailable in streaming")
+        }
+        SelectorError::UnsupportedPseudoClassOrElement => {
+            "the pseudo-class or pseudo-element is unsupported in streaming, since it may need lookahead".to_string()
+        }
+        e => e.to_string(),
+    }
+}
+
+/// The reason why a field can't be streamed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SchemaError {
+    /// The type doesn't derive `FromHtml` as a struct
+    NoSchema,
+    UnknownField(String),
+    NotVec(String),
+    /// The items of the field aren't `item_type`
+    ItemTypeMismatch {
+        field: String,
+        item_type: &'static str,
+    },
+    /// The field has no `select` attribute
+    NoSelector(String),
+    UnsupportedSelector {
+        field: String,
+        selector: String,
+        reason: String,
+    },
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::NoSchema => write!(f, "the type has no schema of fields"),
+            SchemaError::UnknownField(field) => write!(f, "no such field: {field}"),
+            SchemaError::NotVec(field) => write!(f, "{field}: the field isn't Vec"),
+            SchemaError::ItemTypeMismatch { field, item_type } => {
+                write!(f, "{field}: the items of the field aren't {item_type}")
+            }
+            SchemaError::NoSelector(field) => write!(f, "{field}: the field has no selector"),
+            SchemaError::UnsupportedSelector {
+                field,
+                selector,
+                reason,
+            } => write!(
+                f,
+                "{field}: the selector {selector:?} can't be streamed: {reason}"
+            ),
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+#[derive(Debug)]
+pub enum StreamError<E> {
+    Io(std::io::Error),
+    Rewriting(RewritingError),
+    /// The item is captured, but no element is found by parsing it again.
+    /// It happens when the item can't be placed in `<body>`, like `<tr>`
+    NoElement {
+        index: usize,
+    },
+    Item {
+        index: usize,
+        error: E,
+    },
+}
+
+impl<E> Display for StreamError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "failed to read HTML: {e}"),
+            StreamError::Rewriting(e) => write!(f, "failed to stream HTML: {e}"),
+            StreamError::NoElement { index } => {
+                write!(f, "[{index}]: no element is found in the item")
+            }
+            StreamError::Item { index, error } => write!(f, "[{index}]: {error}"),
+        }
+    }
+}
+
+impl<E> Error for StreamError<E> where E: Error {}
@@ -1,224 +1,144 @@
+//! The test cases applicable to any backend are in `h2s::backend::conformance`, which run for each backend.
+//! The cases specific to the default backend remain here.
+
 use h2s::FromHtml;
 
 #[test]
-fn string_values() {
+fn error_snippet() {
     #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct Struct1 {
-        // Single String, attr
-        #[h2s(attr = "lang")]
-        a: String,
-        // Single String, select
-        #[h2s(select = ".b")]
-        b: String,
-        // Single String, attr & select
-        #[h2s(select = ".c", attr = "id")]
-        c: String,
-        // Vec
-        #[h2s(select = ".d")]
-        d: Vec<String>,
-        // Option - Some
-        #[h2s(select = ".e")]
-        e: Option<String>,
-        // Option - None
-        #[h2s(select = ".class-that-does-not-exist")]
-        e_none: Option<String>,
-        // Array
-        #[h2s(select = ".f")]
-        f: [String; 3],
-        #[h2s(select = ".g")]
-        g: Struct2,
+    pub struct Page {
+        #[h2s(select = ".item")]
+        items: Vec<Item>,
     }
 
     #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct Struct2 {
-        // An inner text of struct root element
-        #[h2s()]
-        h: String,
+    pub struct Item {
+        #[h2s(select = ".views")]
+        views: usize,
     }
 
-    let html = r#"
-<!DOCTYPE html>
-<html lang="avalue">
-<body>
-
-<div class="b">bvalue</div>
-
-<div class="c" id="cvalue" />
-
-<div class="d">dvalue1</div>
-<div class="d">dvalue2</div>
-<div class="d">dvalue3</div>
-
-<div class="e">evalue</div>
-
-<div class="f">fvalue1</div>
-<div class="f">fvalue2</div>
-<div class="f">fvalue3</div>
-    
-<div class="g">hvalue</div>
-
-</body>
-</html>
-    "#;
-
-    let res = h2s::parse::<Struct1>(html);
+    let html = r#"<div class="item"><span class="views">1.2k</span></div>"#;
     assert_eq!(
-        res.unwrap(),
-        Struct1 {
-            a: s("avalue"),
-            b: s("bvalue"),
-            c: s("cvalue"),
-            d: vec![s("dvalue1"), s("dvalue2"), s("dvalue3")],
-            e: Some(s("evalue")),
-            e_none: None,
-            f: [s("fvalue1"), s("fvalue2"), s("fvalue3")],
-            g: Struct2 { h: s("hvalue") }
-        }
-    )
+        h2s::parse::<Page>(html).unwrap_err().to_string(),
+        "items: [0] at line 1: views: at line 1: invalid digit found in string",
+        "snippets are not shown by default"
+    );
+    assert_eq!(
+        h2s::parse_with_snippets::<Page>(html, 40)
+            .unwrap_err()
+            .to_string(),
+        "items: [0] at line 1 (<div class=\"item\"><span class=\"views\">1....): \
+         views: at line 1 (<span class=\"views\">1.2k</span>): invalid digit found in string"
+    );
 }
 
 #[test]
-fn nested_struct() {
-    #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct Struct {
-        // Single Struct, parse root element itself
-        #[h2s]
-        a: StructInner1,
-        // Single Struct, select
-        #[h2s(select = ".b")]
-        b: StructInner2,
-        // Vec
-        #[h2s(select = ".c")]
-        c: Vec<StructInner2>,
-        // Option - Some
-        #[h2s(select = ".d")]
-        d: Option<StructInner2>,
-        // Option - None
-        #[h2s(select = ".class-that-does-not-exist")]
-        d_none: Option<StructInner2>,
-        // Array
-        #[h2s(select = ".e")]
-        e: [StructInner2; 3],
-    }
-
+fn unsupported_when_selector() {
     #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct StructInner1 {
-        #[h2s(attr = "lang")]
-        a: String,
+    pub enum Content {
+        // a syntactically valid selector that the backend doesn't support
+        #[h2s(when = ":unsupported-pseudo-class")]
+        Unsupported,
+        Fallback,
     }
 
-    #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct StructInner2 {
-        #[h2s(select = "span")]
-        v: String,
-    }
-
-    let html = r#"
-<!DOCTYPE html>
-<html lang="a">
-<body>
-<div class="b"><span>bvalue</span></div>
+    let err = h2s::parse::<Content>("<div></div>").unwrap_err();
+    assert!(
+        matches!(
+            err.rejected.as_slice(),
+            [h2s::RejectedVariant {
+                reason: h2s::RejectionReason::InvalidSelector(_),
+                ..
+            }]
+        ),
+        "the fallback variant isn't tried: {err:?}"
+    );
+}
 
-<div class="c"><span>cvalue1</span></div>
-<div class="c"><span>cvalue2</span></div>
-<div class="c"><span>cvalue3</span></div>
+#[test]
+#[ignore]
+fn invalid_macro_attribute_combination() {
+    // TODO
+}
 
-<div class="d"><span>dvalue</span></div>
+#[test]
+#[ignore]
+fn macro_error_message() {
+    // TODO add tests of compilation error message
+}
 
-<div class="e"><span>evalue1</span></div>
-<div class="e"><span>evalue2</span></div>
-<div class="e"><span>evalue3</span></div>
-</body>
-</html>
-    "#;
+#[test]
+fn parse_bytes() {
+    use h2s::encoding_rs::{SHIFT_JIS, UTF_8, WINDOWS_1252};
 
-    let res = h2s::parse::<Struct>(html);
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Page {
+        #[h2s(select = "h1")]
+        title: String,
+    }
 
-    let si2 = |s: &str| StructInner2 { v: s.to_string() };
+    let title = |html: &[u8], hint| {
+        let (page, encoding) = h2s::parse_bytes::<Page>(html, hint);
+        (page.unwrap().title, encoding)
+    };
+    assert_eq!(
+        title(
+            b"<meta charset=\"shift_jis\"><h1>\x93\xfa\x96\x7b</h1>",
+            None
+        ),
+        (s("日本"), SHIFT_JIS)
+    );
+    assert_eq!(
+        title(b"<h1>\x93\xfa\x96\x7b</h1>", Some(SHIFT_JIS)),
+        (s("日本"), SHIFT_JIS),
+        "the hint is used without <meta>"
+    );
+    assert_eq!(title(b"<h1>caf\xe9</h1>", None), (s("café"), WINDOWS_1252));
+    assert_eq!(
+        title("\u{feff}<h1>café</h1>".as_bytes(), Some(SHIFT_JIS)),
+        (s("café"), UTF_8)
+    );
 
+    let (page, encoding) = h2s::parse_bytes::<Page>(b"<meta charset=euc-kr>", None);
+    assert!(page.is_err());
     assert_eq!(
-        res.unwrap(),
-        Struct {
-            a: StructInner1 { a: s("a") },
-            b: si2("bvalue"),
-            c: vec![si2("cvalue1"), si2("cvalue2"), si2("cvalue3")],
-            d: Some(si2("dvalue")),
-            d_none: None,
-            e: [si2("evalue1"), si2("evalue2"), si2("evalue3")],
-        }
-    )
+        encoding.name(),
+        "EUC-KR",
+        "the encoding is reported even if the parsing fails"
+    );
 }
 
 #[test]
-fn struct_unnamed() {
-    #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct Struct(
-        // Single Struct, parse root element itself
-        #[h2s] StructInner1,
-        // Single Struct, select
-        #[h2s(select = ".b")] StructInner2,
-        // Vec
-        #[h2s(select = ".c")] Vec<StructInner2>,
-        // Option - Some
-        #[h2s(select = ".d")] Option<StructInner2>,
-        // Option - None
-        #[h2s(select = ".class-that-does-not-exist")] Option<StructInner2>,
-        // Array
-        #[h2s(select = ".e")] [StructInner2; 3],
-    );
-
-    #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct StructInner1(#[h2s(attr = "lang")] String);
+fn parse_all() {
+    use h2s::backend::scraper::Scraper;
+    use h2s::Document;
 
     #[derive(FromHtml, Debug, Eq, PartialEq)]
-    pub struct StructInner2(#[h2s(select = "span")] String);
+    pub struct Item {
+        #[h2s(select = ".views")]
+        views: usize,
+    }
 
     let html = r#"
-<!DOCTYPE html>
-<html lang="a">
-<body>
-<div class="b"><span>bvalue</span></div>
-
-<div class="c"><span>cvalue1</span></div>
-<div class="c"><span>cvalue2</span></div>
-<div class="c"><span>cvalue3</span></div>
-
-<div class="d"><span>dvalue</span></div>
-
-<div class="e"><span>evalue1</span></div>
-<div class="e"><span>evalue2</span></div>
-<div class="e"><span>evalue3</span></div>
-</body>
-</html>
-    "#;
-
-    let res = h2s::parse::<Struct>(html);
-
-    let si2 = |s: &str| StructInner2(s.to_string());
-
+<div class="item"><span class="views">1</span></div>
+<div class="item"><span class="views">1.2k</span></div>
+<div class="item"><span class="views">3</span></div>
+"#;
     assert_eq!(
-        res.unwrap(),
-        Struct(
-            StructInner1(s("a")),
-            si2("bvalue"),
-            vec![si2("cvalue1"), si2("cvalue2"), si2("cvalue3")],
-            Some(si2("dvalue")),
-            None,
-            [si2("evalue1"), si2("evalue2"), si2("evalue3")],
-        )
-    )
-}
-
-#[test]
-#[ignore]
-fn invalid_macro_attribute_combination() {
-    // TODO
-}
+        h2s::parse_all::<Item>(html, ".item")
+            .unwrap_err()
+            .to_string(),
+        "[1] at line 3: views: at line 3: invalid digit found in string"
+    );
 
-#[test]
-#[ignore]
-fn macro_error_message() {
-    // TODO add tests of compilation error message
+    let doc = Document::<Scraper>::parse(html);
+    assert_eq!(
+        h2s::iter::<Item, _>(&doc, ".item")
+            .unwrap()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>(),
+        vec![Item { views: 1 }, Item { views: 3 }]
+    );
 }
 
 fn s(s: &str) -> String {
@@ -0,0 +1,192 @@
+#![cfg(feature = "streaming")]
+
+use std::io::Read;
+
+use h2s::streaming::{stream_items, SchemaError, StreamError};
+use h2s::FromHtml;
+
+#[derive(FromHtml, Debug, Eq, PartialEq)]
+pub struct Page {
+    #[h2s(select = "h1")]
+    title: String,
+    #[h2s(select = ".articles > div")]
+    articles: Vec<Article>,
+}
+
+#[derive(FromHtml, Debug, Eq, PartialEq)]
+pub struct Article {
+    #[h2s(select = "h2 > a")]
+    title: String,
+    #[h2s(select = "h2 > a", attr = "href")]
+    url: String,
+    #[h2s(select = ".tag")]
+    tags: Vec<String>,
+}
+
+fn page_html(articles: usize) -> String {
+    let articles: String = (0..articles)
+        .map(|i| {
+            format!(
+                r#"<div><h2><a href="/{i}?a=1&amp;b=&quot;2&quot;">Article {i}</a></h2><p>x &lt; y</p><span class="tag">t{i}</span><img src="{i}.png"><span class="tag">all</span></div>"#
+            )
+        })
+        .collect();
+    format!(
+        r#"<!DOCTYPE html><html><body><h1>Title</h1><div class="articles">{articles}</div></body></html>"#
+    )
+}
+
+fn article(i: usize) -> Article {
+    Article {
+        title: format!("Article {i}"),
+        url: format!("/{i}?a=1&b=\"2\""),
+        tags: vec![format!("t{i}"), "all".to_string()],
+    }
+}
+
+#[test]
+fn items() {
+    let html = page_html(3);
+    let items = stream_items::<Page, Article, _>(html.as_bytes(), "articles")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(items, (0..3).map(article).collect::<Vec<_>>());
+
+    // the same result as parsing the whole document
+    let page = h2s::parse::<Page>(&html).unwrap();
+    assert_eq!(page.articles, items);
+}
+
+/// A reader returning a few bytes at a time, so that tags are split across chunks
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.0.len().min(buf.len()).min(7);
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn chunked_input() {
+    let html = page_html(2000);
+    let mut items = stream_items::<Page, Article, _>(Trickle(html.as_bytes()), "articles").unwrap();
+    for i in 0..2000 {
+        assert_eq!(items.next().unwrap().unwrap(), article(i));
+    }
+    assert!(items.next().is_none());
+}
+
+#[test]
+fn root_and_nested_items() {
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    #[h2s(root = "main")]
+    pub struct List {
+        #[h2s(select = "li")]
+        items: Vec<Item>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Item {
+        #[h2s(select = "span")]
+        names: Vec<String>,
+    }
+
+    let html = r#"<li><span>outside</span></li><main><ul><li><span>a</span><ul><li><span>b</span></li></ul></li><li><span>c</span></main>"#;
+    let names: Vec<Vec<String>> = stream_items::<List, Item, _>(html.as_bytes(), "items")
+        .unwrap()
+        .map(|item| item.unwrap().names)
+        .collect();
+    // a nested item comes before its ancestor, and the item without end tag is completed by the end of the document
+    assert_eq!(names, vec![vec!["b"], vec!["a", "b"], vec!["c"]]);
+}
+
+#[test]
+fn item_error() {
+    let html = r#"<div class="articles"><div><h2><a href="/">ok</a></h2></div><div></div></div>"#;
+    let results: Vec<_> = stream_items::<Page, Article, _>(html.as_bytes(), "articles")
+        .unwrap()
+        .collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(StreamError::Item { index, .. }) => assert_eq!(*index, 1),
+        r => panic!("unexpected result: {r:?}"),
+    }
+}
+
+#[test]
+fn schema_errors() {
+    // only the schema is used
+    #[allow(dead_code)]
+    #[derive(FromHtml, Debug)]
+    pub struct Siblings {
+        #[h2s(select = "h1 + div")]
+        items: Vec<Article>,
+        #[h2s(select = "li:last-child")]
+        last: Vec<Article>,
+        #[h2s(select = "h1")]
+        title: String,
+    }
+
+    let stream = |field: &str| {
+        stream_items::<Siblings, Article, _>(&b""[..], field)
+            .err()
+            .unwrap()
+    };
+    match stream("items") {
+        SchemaError::UnsupportedSelector {
+            field, selector, ..
+        } => {
+            assert_eq!(field, "items");
+            assert_eq!(selector, "h1 + div");
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+    assert!(matches!(
+        stream("last"),
+        SchemaError::UnsupportedSelector { .. }
+    ));
+    assert_eq!(stream("title"), SchemaError::NotVec("title".to_string()));
+    assert!(matches!(
+        stream_items::<Page, Page, _>(&b""[..], "articles").err().unwrap(),
+        SchemaError::ItemTypeMismatch { field, .. } if field == "articles"
+    ));
+    assert_eq!(
+        stream("nothing"),
+        SchemaError::UnknownField("nothing".to_string())
+    );
+}
+
+#[test]
+fn omitted_end_tags() {
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct List {
+        #[h2s(select = "li")]
+        items: Vec<Text>,
+        #[h2s(select = "p")]
+        paragraphs: Vec<Text>,
+    }
+
+    #[derive(FromHtml, Debug, Eq, PartialEq)]
+    pub struct Text(#[h2s] String);
+
+    let stream = |html: &str, field: &str| -> Vec<String> {
+        stream_items::<List, Text, _>(html.as_bytes(), field)
+            .unwrap()
+            .map(|item| item.unwrap().0)
+            .collect()
+    };
+    assert_eq!(
+        stream("<ul><li>a<li>b<li>c</ul><p>after</p>", "items"),
+        vec!["a", "b", "c"],
+        "the items are completed by the following sibling or the end tag of the ancestor"
+    );
+    assert_eq!(
+        stream("<div><p>a<p>b<ul><li>x</ul><p>c</div><p>d", "paragraphs"),
+        vec!["a", "b", "c", "d"]
+    );
+}